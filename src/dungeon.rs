@@ -0,0 +1,29 @@
+use crate::entity::Entity;
+use crate::level::Level;
+
+pub const FLOORS: usize = 5;
+
+// a floor the player is not on, the current floor lives directly on GameState
+pub struct Floor {
+    pub level: Level,
+    pub entities: Vec<Entity>, // everything but the player
+    pub floor_items: Vec<(glam::U16Vec2, usize)>,
+}
+
+// every floor gets its own rng so floors come out the same whatever order they are visited in
+pub fn floor_seed(seed: u64, depth: usize) -> u64 {
+    seed ^ (depth as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+pub fn monsters(depth: usize) -> usize {
+    8 + 4 * depth
+}
+
+pub fn items(depth: usize) -> usize {
+    24usize.saturating_sub(4 * depth).max(6)
+}
+
+// deeper floors are split into smaller rooms more often
+pub fn split_bonus(depth: usize) -> f32 {
+    0.05 * depth as f32
+}
@@ -1,13 +1,21 @@
 use indexmap::IndexMap;
 
+use crate::game::{screen_to_display, GameState};
+use crate::input::Input;
 use crate::level::Level;
-use crate::level::RoomTile;
+use crate::level::ROOM_SIZES;
+use crate::level::{import_rooms, RoomTile};
 use crate::tile::Tile;
+use crate::ui::{Menu, Ui};
 use crate::util::import_toml;
 use std::io::Write;
 
+pub const DEFAULT_ROOM_SIZE: u8 = 3;
+const MAX_UNDO: usize = 100;
+const RUN_STEP: i16 = 4;
+
 fn to_room_size(n: u8) -> glam::U16Vec2 {
-    let size = (2 << (n as u8)) + 1;
+    let size = (1 << n) + 1;
     glam::u16vec2(size, size)
 }
 
@@ -29,7 +37,7 @@ pub fn export(name: String, tiles: &IndexMap<String, Tile>, level: &Level) -> an
     }
     tiles_used.sort();
 
-    println!("hi, {tiles_used:?}");
+    tracing::info!("tiles used {tiles_used:?}");
 
     let mut data = level.data.clone();
     for i in 0..level.size.y {
@@ -51,7 +59,7 @@ pub fn export(name: String, tiles: &IndexMap<String, Tile>, level: &Level) -> an
     };
 
     let path = format!("res/room_size_{size}.toml");
-    let mut rooms = import_toml::<RoomTile>(&path);
+    let mut rooms = import_toml::<RoomTile>(&path)?;
 
     // update if room with that name already exist else create it
     if rooms.contains_key(&name) {
@@ -60,18 +68,422 @@ pub fn export(name: String, tiles: &IndexMap<String, Tile>, level: &Level) -> an
         rooms.insert(name, room);
     }
 
-    println!("{rooms:?}");
+    tracing::info!("{rooms:?}");
 
     // write
     let mut file = std::fs::File::create(&path)?;
     let toml = toml::to_string(&rooms)?;
-    file.write(toml.as_bytes())?;
+    file.write_all(toml.as_bytes())?;
 
     Ok(())
 }
 
-// let mut level = level::Level::new(glam::u16vec2(3, 3));
-// let mut level = level::Level::new(glam::u16vec2(5, 5));
-// let mut level = level::Level::new(glam::u16vec2(9, 9));
-// let mut level = level::Level::new(glam::u16vec2(17, 17));
-// let mut level = level::Level::new(glam::u16vec2(33, 33));
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Brush {
+    Point,
+    Line,
+    Rect,
+    Fill,
+}
+
+impl Brush {
+    fn next(self) -> Self {
+        match self {
+            Brush::Point => Brush::Line,
+            Brush::Line => Brush::Rect,
+            Brush::Rect => Brush::Fill,
+            Brush::Fill => Brush::Point,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Brush::Point => "point",
+            Brush::Line => "line",
+            Brush::Rect => "rectangle",
+            Brush::Fill => "flood fill",
+        }
+    }
+}
+
+pub struct Editor {
+    pub cursor: glam::U16Vec2,
+    pub room_size: u8,
+    pub brush: Brush,
+    // first corner of a line or rectangle waiting for its second
+    pub anchor: Option<glam::U16Vec2>,
+    pub palette: Menu,
+    // picking a saved room to open, with the (size, name) of each entry
+    pub open_menu: Option<(Menu, Vec<(u8, String)>)>,
+    undo: Vec<Vec<Vec<usize>>>,
+    redo: Vec<Vec<Vec<usize>>>,
+}
+
+impl Editor {
+    pub fn new(tiles: &IndexMap<String, Tile>) -> Self {
+        let items = tiles
+            .iter()
+            .map(|(key, tile)| {
+                let mut vec = vec![*tile, Tile::new(' ', 0, 0, false)];
+                vec.extend(Tile::from_string(key, Some(15), Some(0)));
+                vec
+            })
+            .collect();
+        let mut palette = Menu::new("Tiles", glam::u16vec2(1, 1), glam::u16vec2(30, 15), items);
+        palette.selection = tiles.get_index_of("tile").unwrap_or(0);
+
+        Self {
+            cursor: glam::U16Vec2::ZERO,
+            room_size: DEFAULT_ROOM_SIZE,
+            brush: Brush::Point,
+            anchor: None,
+            palette,
+            open_menu: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+// looks through every size for a room with this name
+pub fn find_room(name: &str) -> anyhow::Result<Option<u8>> {
+    Ok(import_rooms()?
+        .iter()
+        .position(|rooms| rooms.contains_key(name))
+        .map(|size| size as u8))
+}
+
+// a blank room of the given size filled with the "tile" tile
+pub fn blank_room(size: u8, tiles: &IndexMap<String, Tile>) -> Level {
+    let mut level = Level::new(to_room_size(size));
+    let tile = tiles.get_index_of("tile").unwrap_or(0);
+    for row in level.data.iter_mut() {
+        row.fill(tile);
+    }
+    level
+}
+
+impl GameState {
+    pub fn editor_input(&mut self, input: Input) {
+        if self.editor.is_none() {
+            return;
+        }
+        if self.editor_mut().open_menu.is_some() {
+            self.open_menu_input(input);
+            return;
+        }
+        let mut delta = glam::i16vec2(0, 0);
+        match input {
+            // the first quit only drops a pending line or rectangle
+            Input::Quit => self.quit = self.editor_mut().anchor.take().is_none(),
+            Input::Left => delta = glam::i16vec2(-1, 0),
+            Input::Right => delta = glam::i16vec2(1, 0),
+            Input::Up => delta = glam::i16vec2(0, -1),
+            Input::Down => delta = glam::i16vec2(0, 1),
+            Input::UpLeft => delta = glam::i16vec2(-1, -1),
+            Input::UpRight => delta = glam::i16vec2(1, -1),
+            Input::DownLeft => delta = glam::i16vec2(-1, 1),
+            Input::DownRight => delta = glam::i16vec2(1, 1),
+            Input::Run(x, y) => delta = glam::i16vec2(x, y) * RUN_STEP,
+            Input::MenuPrev => self.editor_mut().palette.prev(),
+            Input::MenuNext => self.editor_mut().palette.next(),
+            Input::Select => {
+                let cursor = self.editor_mut().cursor;
+                self.apply_brush(cursor);
+            }
+            Input::Brush => {
+                let editor = self.editor_mut();
+                editor.brush = editor.brush.next();
+                editor.anchor = None;
+                let text = format!("Brush: {}.", editor.brush.name());
+                self.message(text);
+            }
+            Input::Eyedropper => {
+                let cursor = self.editor_mut().cursor;
+                let tile = self.level.data[cursor.y as usize][cursor.x as usize];
+                self.editor_mut().palette.selection = tile;
+            }
+            Input::Undo => {
+                if let Some(data) = self.editor_mut().undo.pop() {
+                    let data = std::mem::replace(&mut self.level.data, data);
+                    self.editor_mut().redo.push(data);
+                }
+            }
+            Input::Redo => {
+                if let Some(data) = self.editor_mut().redo.pop() {
+                    let data = std::mem::replace(&mut self.level.data, data);
+                    self.editor_mut().undo.push(data);
+                }
+            }
+            Input::RoomSize => {
+                let size = (self.editor_mut().room_size + 1) % ROOM_SIZES;
+                self.set_room_size(size);
+            }
+            Input::Save => self.save_room(),
+            Input::OpenRoom => {
+                let rooms = match import_rooms() {
+                    Ok(rooms) => rooms,
+                    Err(error) => {
+                        self.message(format!("Could not list rooms: {error:#}"));
+                        return;
+                    }
+                };
+                let rooms: Vec<(u8, String)> = rooms
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(size, rooms)| rooms.into_keys().map(move |x| (size as u8, x)))
+                    .collect();
+                let items = rooms
+                    .iter()
+                    .map(|(size, name)| {
+                        Tile::from_string(format!("{size} {name}"), Some(15), Some(0))
+                    })
+                    .collect();
+                let size = glam::u16vec2(30, 15);
+                let position = glam::u16vec2(self.display.size.x.saturating_sub(size.x + 2), 1);
+                self.editor_mut().open_menu =
+                    Some((Menu::new("Open room", position, size, items), rooms));
+            }
+            Input::EnterText => self.text_input = true,
+            Input::Resize(x, y) => self.resize(glam::u16vec2(x, y)),
+            Input::Click(x, y) => self.editor_click(glam::u16vec2(x, y), false),
+            Input::RightClick(x, y) => self.editor_click(glam::u16vec2(x, y), true),
+            _ => {}
+        }
+
+        let max = self.level.size.as_i16vec2() - 1;
+        let editor = self.editor_mut();
+        editor.cursor = (editor.cursor.as_i16vec2() + delta)
+            .clamp(glam::I16Vec2::ZERO, max)
+            .as_u16vec2();
+    }
+
+    fn editor_mut(&mut self) -> &mut Editor {
+        self.editor.as_mut().unwrap()
+    }
+
+    // left paints with the brush and right only moves the cursor
+    fn editor_click(&mut self, position: glam::U16Vec2, right: bool) {
+        let Some(position) = screen_to_display(self.display.size, position) else {
+            return;
+        };
+        if let Some(item) = self.editor_mut().palette.item_at(position) {
+            self.editor_mut().palette.selection = item;
+            return;
+        }
+        let cell = self.editor_camera() + position.as_i16vec2();
+        if cell.x < 0
+            || cell.y < 0
+            || cell.x >= self.level.size.x as i16
+            || cell.y >= self.level.size.y as i16
+        {
+            return;
+        }
+        self.editor_mut().cursor = cell.as_u16vec2();
+        if !right {
+            self.apply_brush(cell.as_u16vec2());
+        }
+    }
+
+    fn open_menu_input(&mut self, input: Input) {
+        let display_size = self.display.size;
+        let Some((menu, rooms)) = self.editor_mut().open_menu.as_mut() else {
+            return;
+        };
+        let chosen = match input {
+            Input::Up | Input::MenuPrev => {
+                menu.prev();
+                None
+            }
+            Input::Down | Input::MenuNext => {
+                menu.next();
+                None
+            }
+            Input::Select => Some(menu.selection),
+            Input::Click(x, y) => {
+                screen_to_display(display_size, glam::u16vec2(x, y)).and_then(|x| menu.item_at(x))
+            }
+            Input::Quit | Input::OpenRoom => {
+                self.editor_mut().open_menu = None;
+                return;
+            }
+            _ => None,
+        };
+        if let Some((size, name)) = chosen.and_then(|x| rooms.get(x)).cloned() {
+            self.editor_mut().open_menu = None;
+            if let Err(error) = self.open_room(size, &name) {
+                self.message(format!("Could not open room: {error:#}"));
+            }
+        }
+    }
+
+    // replaces the room being edited with a saved one, saving keeps its name unless renamed
+    pub fn open_room(&mut self, size: u8, name: &str) -> anyhow::Result<()> {
+        let rooms = import_toml::<RoomTile>(&format!("res/room_size_{size}.toml"))?;
+        let room = rooms
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("no room {name} of size {size}"))?;
+        if room.size != size {
+            anyhow::bail!(
+                "room {name} is size {} but saved with size {size} rooms",
+                room.size
+            );
+        }
+        if let Some(tile) = room.tiles.iter().find(|x| !self.tiles.contains_key(*x)) {
+            anyhow::bail!("room {name} uses unknown tile {tile}");
+        }
+        let data = room
+            .resolve(&self.tiles)
+            .ok_or_else(|| anyhow::anyhow!("room {name} does not match its size"))?;
+
+        self.set_room_size(size);
+        self.level.data = data;
+        self.name = name.to_string();
+        self.message(format!("Opened room {name}."));
+        Ok(())
+    }
+
+    pub fn set_room_size(&mut self, size: u8) {
+        self.level = blank_room(size, &self.tiles);
+        if let Some(editor) = self.editor.as_mut() {
+            editor.room_size = size;
+            editor.cursor = glam::U16Vec2::ZERO;
+            editor.anchor = None;
+            editor.undo.clear();
+            editor.redo.clear();
+        }
+        let span = to_room_size(size).x;
+        self.message(format!("Room size {size} ({span}x{span})."));
+    }
+
+    fn save_room(&mut self) {
+        if self.name.is_empty() {
+            self.message("Name the room before saving it.");
+            return;
+        }
+        match export(self.name.clone(), &self.tiles, &self.level) {
+            Ok(()) => self.message(format!("Saved room {}.", self.name)),
+            Err(error) => self.message(format!("Could not save room: {error:#}")),
+        }
+    }
+
+    fn apply_brush(&mut self, position: glam::U16Vec2) {
+        let Some(editor) = self.editor.as_mut() else {
+            return;
+        };
+        let tile = editor.palette.selection;
+        let cells = match (editor.brush, editor.anchor) {
+            (Brush::Point, _) => vec![position],
+            (Brush::Line | Brush::Rect, None) => {
+                editor.anchor = Some(position);
+                return;
+            }
+            (Brush::Line, Some(anchor)) => line(anchor, position),
+            (Brush::Rect, Some(anchor)) => rect(anchor, position),
+            (Brush::Fill, _) => flood(&self.level, position),
+        };
+        editor.anchor = None;
+
+        editor.undo.push(self.level.data.clone());
+        if editor.undo.len() > MAX_UNDO {
+            editor.undo.remove(0);
+        }
+        editor.redo.clear();
+        for cell in cells {
+            self.level.data[cell.y as usize][cell.x as usize] = tile;
+        }
+    }
+
+    // the level position drawn at the top left of the display
+    fn editor_camera(&self) -> glam::I16Vec2 {
+        let cursor = self.editor.as_ref().map(|x| x.cursor).unwrap_or_default();
+        cursor.as_i16vec2() - (self.display.size / 2).as_i16vec2()
+    }
+
+    pub fn draw_editor(&mut self) {
+        let Some(editor) = self.editor.as_ref() else {
+            return;
+        };
+        let camera = self.editor_camera();
+        for (i, row) in self.display.data.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                let position = camera + glam::i16vec2(j as i16, i as i16);
+                *cell = if position.x < 0
+                    || position.y < 0
+                    || position.x >= self.level.size.x as i16
+                    || position.y >= self.level.size.y as i16
+                {
+                    Tile::new(' ', 0, 0, false)
+                } else {
+                    self.tiles[self.level.data[position.y as usize][position.x as usize]]
+                };
+                // the cursor and anchor are shown with their colours swapped
+                let position = position.as_u16vec2();
+                if position == editor.cursor || Some(position) == editor.anchor {
+                    (cell.fore, cell.back) = (cell.back, cell.fore.max(1));
+                }
+            }
+        }
+        editor.palette.render_to(&mut self.display);
+        if let Some((menu, _)) = &editor.open_menu {
+            menu.render_to(&mut self.display);
+        }
+    }
+}
+
+fn line(from: glam::U16Vec2, to: glam::U16Vec2) -> Vec<glam::U16Vec2> {
+    let (from, to) = (from.as_i16vec2(), to.as_i16vec2());
+    let delta = to - from;
+    let steps = delta.x.abs().max(delta.y.abs()).max(1);
+    (0..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            glam::u16vec2(
+                (from.x as f32 + delta.x as f32 * t).round() as u16,
+                (from.y as f32 + delta.y as f32 * t).round() as u16,
+            )
+        })
+        .collect()
+}
+
+// the outline of the rectangle with these corners
+fn rect(a: glam::U16Vec2, b: glam::U16Vec2) -> Vec<glam::U16Vec2> {
+    let (min, max) = (a.min(b), a.max(b));
+    let mut cells = Vec::new();
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            if x == min.x || x == max.x || y == min.y || y == max.y {
+                cells.push(glam::u16vec2(x, y));
+            }
+        }
+    }
+    cells
+}
+
+// every cell joined to start by cells of the same tile
+fn flood(level: &Level, start: glam::U16Vec2) -> Vec<glam::U16Vec2> {
+    let target = level.data[start.y as usize][start.x as usize];
+    let mut seen = vec![vec![false; level.size.x as usize]; level.size.y as usize];
+    let mut stack = vec![start];
+    let mut cells = Vec::new();
+    seen[start.y as usize][start.x as usize] = true;
+    while let Some(cell) = stack.pop() {
+        cells.push(cell);
+        for delta in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = cell.as_i16vec2() + glam::i16vec2(delta.0, delta.1);
+            if next.x < 0
+                || next.y < 0
+                || next.x >= level.size.x as i16
+                || next.y >= level.size.y as i16
+            {
+                continue;
+            }
+            let (x, y) = (next.x as usize, next.y as usize);
+            if !seen[y][x] && level.data[y][x] == target {
+                seen[y][x] = true;
+                stack.push(next.as_u16vec2());
+            }
+        }
+    }
+    cells
+}
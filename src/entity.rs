@@ -0,0 +1,39 @@
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct Entity {
+    pub r#char: char,
+    pub fore: u8,
+    // takes the back colour of the tile underneath when not set
+    #[serde(default)]
+    pub back: Option<u8>,
+    #[serde(default = "default_health")]
+    pub health: u16,
+    #[serde(default)]
+    pub attack: u16,
+    // energy gained per tick
+    #[serde(default = "default_speed")]
+    pub speed: i32,
+    #[serde(default)]
+    pub energy: i32,
+    #[serde(default)]
+    pub position: glam::U16Vec2,
+    #[serde(default)]
+    pub kind: String,
+}
+
+fn default_health() -> u16 {
+    10
+}
+
+fn default_speed() -> i32 {
+    10
+}
+
+impl Entity {
+    pub fn spawn(&self, kind: &str, position: glam::U16Vec2) -> Self {
+        Self {
+            position,
+            kind: kind.to_string(),
+            ..self.clone()
+        }
+    }
+}
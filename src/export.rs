@@ -0,0 +1,140 @@
+use crate::level::Level;
+use crate::tile::Tile;
+use indexmap::IndexMap;
+use std::fmt::Write;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Ansi,
+    Html,
+    Text,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Ansi, Format::Html, Format::Text];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Ansi => "ans",
+            Format::Html => "html",
+            Format::Text => "txt",
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.')?.1.to_lowercase();
+        Self::ALL.into_iter().find(|x| x.extension() == extension)
+    }
+}
+
+// the whole level, ignoring what has been seen
+pub fn level_tiles(level: &Level, tiles: &IndexMap<String, Tile>) -> Vec<Vec<Tile>> {
+    level
+        .data
+        .iter()
+        .map(|row| row.iter().map(|x| tiles[*x]).collect())
+        .collect()
+}
+
+pub fn render(grid: &[Vec<Tile>], format: Format) -> String {
+    match format {
+        Format::Ansi => ansi(grid),
+        Format::Html => html(grid),
+        Format::Text => text(grid),
+    }
+}
+
+pub fn write(path: &str, grid: &[Vec<Tile>], format: Format) -> anyhow::Result<()> {
+    std::fs::write(path, render(grid, format))?;
+    Ok(())
+}
+
+fn ansi(grid: &[Vec<Tile>]) -> String {
+    let mut out = String::new();
+    for row in grid {
+        // colours are only sent when they change along the row
+        let mut colours = None;
+        for tile in row {
+            if colours != Some((tile.fore, tile.back)) {
+                write!(out, "\x1b[38;5;{}m\x1b[48;5;{}m", tile.fore, tile.back).unwrap();
+                colours = Some((tile.fore, tile.back));
+            }
+            out.push(tile.char);
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+fn html(grid: &[Vec<Tile>]) -> String {
+    let mut out = String::from("<pre style=\"font-family: monospace; line-height: 1;\">\n");
+    for row in grid {
+        let mut colours = None;
+        for tile in row {
+            if colours != Some((tile.fore, tile.back)) {
+                if colours.is_some() {
+                    out.push_str("</span>");
+                }
+                write!(
+                    out,
+                    "<span style=\"color: {}; background: {};\">",
+                    hex(tile.fore),
+                    hex(tile.back)
+                )
+                .unwrap();
+                colours = Some((tile.fore, tile.back));
+            }
+            match tile.char {
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '&' => out.push_str("&amp;"),
+                c => out.push(c),
+            }
+        }
+        if colours.is_some() {
+            out.push_str("</span>");
+        }
+        out.push('\n');
+    }
+    out.push_str("</pre>\n");
+    out
+}
+
+// anything outside ascii becomes `#` if it blocks movement and `.` if not
+fn text(grid: &[Vec<Tile>]) -> String {
+    let mut out = String::new();
+    for row in grid {
+        let line: String = row
+            .iter()
+            .map(|tile| match tile.char {
+                c if c.is_ascii() => c,
+                _ if tile.r#move => '.',
+                _ => '#',
+            })
+            .collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+// the xterm 256 colour palette
+fn hex(colour: u8) -> String {
+    const SYSTEM: [u32; 16] = [
+        0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xc0c0c0, 0x808080,
+        0xff0000, 0x00ff00, 0xffff00, 0x0000ff, 0xff00ff, 0x00ffff, 0xffffff,
+    ];
+    const CUBE: [u32; 6] = [0, 95, 135, 175, 215, 255];
+    let rgb = match colour {
+        0..=15 => SYSTEM[colour as usize],
+        16..=231 => {
+            let i = colour as usize - 16;
+            (CUBE[i / 36] << 16) | (CUBE[i / 6 % 6] << 8) | CUBE[i % 6]
+        }
+        _ => {
+            let grey = 8 + 10 * (colour as u32 - 232);
+            (grey << 16) | (grey << 8) | grey
+        }
+    };
+    format!("#{rgb:06x}")
+}
@@ -0,0 +1,98 @@
+// recursive shadowcasting, see
+// https://www.roguebasin.com/index.php/FOV_using_recursive_shadowcasting
+
+// multipliers for transforming coordinates into each octant
+const OCTANTS: [[i32; 4]; 8] = [
+    [1, 0, 0, 1],
+    [0, 1, 1, 0],
+    [0, -1, 1, 0],
+    [-1, 0, 0, 1],
+    [-1, 0, 0, -1],
+    [0, -1, -1, 0],
+    [0, 1, -1, 0],
+    [1, 0, 0, -1],
+];
+
+pub fn compute<F: Fn(usize, usize) -> bool>(
+    size: glam::U16Vec2,
+    origin: glam::U16Vec2,
+    radius: u16,
+    opaque: F,
+) -> Vec<Vec<bool>> {
+    let mut fov = Fov {
+        size: size.as_ivec2(),
+        origin: origin.as_ivec2(),
+        radius: radius as i32,
+        opaque,
+        visible: vec![vec![false; size.x as usize]; size.y as usize],
+    };
+    if origin.x < size.x && origin.y < size.y {
+        fov.visible[origin.y as usize][origin.x as usize] = true;
+    }
+    for octant in OCTANTS {
+        fov.cast_light(1, 1.0, 0.0, octant);
+    }
+    fov.visible
+}
+
+struct Fov<F: Fn(usize, usize) -> bool> {
+    size: glam::IVec2,
+    origin: glam::IVec2,
+    radius: i32,
+    opaque: F,
+    visible: Vec<Vec<bool>>,
+}
+
+impl<F: Fn(usize, usize) -> bool> Fov<F> {
+    fn is_opaque(&self, pos: glam::IVec2) -> bool {
+        pos.x < 0
+            || pos.y < 0
+            || pos.x >= self.size.x
+            || pos.y >= self.size.y
+            || (self.opaque)(pos.x as usize, pos.y as usize)
+    }
+
+    fn cast_light(&mut self, row: i32, mut start: f32, end: f32, [xx, xy, yx, yy]: [i32; 4]) {
+        if start < end {
+            return;
+        }
+        let mut new_start = 0.0;
+        for j in row..=self.radius {
+            let dy = -j;
+            let mut blocked = false;
+            for dx in -j..=0 {
+                let pos = self.origin + glam::ivec2(dx * xx + dy * xy, dx * yx + dy * yy);
+                let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+                let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+                if start < right_slope {
+                    continue;
+                } else if end > left_slope {
+                    break;
+                }
+
+                let in_bounds =
+                    pos.x >= 0 && pos.y >= 0 && pos.x < self.size.x && pos.y < self.size.y;
+                if in_bounds && dx * dx + dy * dy <= self.radius * self.radius {
+                    self.visible[pos.y as usize][pos.x as usize] = true;
+                }
+
+                let opaque = self.is_opaque(pos);
+                if blocked {
+                    if opaque {
+                        new_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = new_start;
+                    }
+                } else if opaque && j < self.radius {
+                    blocked = true;
+                    self.cast_light(j + 1, start, left_slope, [xx, xy, yx, yy]);
+                    new_start = right_slope;
+                }
+            }
+            if blocked {
+                break;
+            }
+        }
+    }
+}
@@ -1,61 +1,306 @@
 use crate::renderer::Display;
-use crate::ui::Ui;
+use crate::ui::{Menu, Ui};
 use crate::{
+    dungeon::{self, Floor},
+    editor::Editor,
+    entity::Entity,
+    export, fov,
     input::{GetInput, Input, TextInput},
-    level::Level,
+    item::Item,
+    level::{Level, RoomTile, LEVEL_SIZE},
+    nutrition::{Deficiency, Nutrient, Nutrition},
     renderer::Renderer,
+    save,
+    skills::{Skill, Skills},
     tile::Tile,
+    turn::{self, Action, ActionCost},
+    util::GameRng,
 };
 use indexmap::IndexMap;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 
 pub struct GameState {
     pub inputs: Box<dyn GetInput>,
     pub display: Display,
-    pub health: u16,
-    pub hunger: u16, // action points lmao
+    pub nutrition: Nutrition,
+    pub skills: Skills,
+    pub deficiencies: Vec<Deficiency>,
+    pub messages: Vec<String>,
+    pub visible: Vec<Vec<bool>>,
+    pub seed: u64,
+    pub rng: GameRng,
+    pub depth: usize,
+    pub floors: Vec<Option<Floor>>, // floors other than the current one, by depth
+    pub room_templates: Vec<IndexMap<String, RoomTile>>,
     pub quit: bool,
-    pub position: glam::U16Vec2,
     pub level: Level,
     pub number: String,
     pub tiles: IndexMap<String, Tile>,
-    pub ui: Vec<crate::ui::Menu>,
+    pub entity_types: IndexMap<String, Entity>,
+    pub entities: Vec<Entity>, // the player is always entities[PLAYER]
+    pub item_types: IndexMap<String, Item>,
+    pub action_costs: IndexMap<String, ActionCost>,
+    pub floor_items: Vec<(glam::U16Vec2, usize)>,
+    pub inventory: Vec<usize>,
+    pub held: Option<usize>, // index into inventory
+    pub inventory_menu: Option<Menu>,
+    pub resume_menu: Option<Menu>, // offered on startup when a save exists
+    pub editor: Option<Editor>,
+    pub ui: Vec<Menu>,
     pub name: String,
     pub text_input: bool,
 }
 
+pub const PLAYER: usize = 0;
+
+pub enum MoveOutcome {
+    Moved,
+    Wall,
+    Creature(usize),
+    Door(glam::U16Vec2),
+}
+
+const MAX_MESSAGES: usize = 100;
+// status lines above the display plus one spare at the bottom
+const STATUS_ROWS: u16 = 2;
+const HUD_ROWS: u16 = STATUS_ROWS + 1;
+const RUN_LIMIT: usize = 100;
+const MONSTER_SIGHT: i16 = 8;
+
 impl GameState {
     pub fn init(
         renderer: &dyn Renderer,
         inputs: Box<dyn GetInput>,
         level: Level,
         tiles: IndexMap<String, Tile>,
-    ) -> Self {
-        let mut size = renderer.resize().unwrap();
-        size.y -= 3;
-        let display = Display::new(size);
+        entity_types: IndexMap<String, Entity>,
+        item_types: IndexMap<String, Item>,
+        action_costs: IndexMap<String, ActionCost>,
+    ) -> anyhow::Result<Self> {
+        let display = Display::new(display_size(renderer.resize()?));
 
-        Self {
+        let mut state = Self {
             display,
             inputs,
-            health: 160,
-            hunger: 255,
+            nutrition: Nutrition::full(),
+            skills: Skills::default(),
+            deficiencies: Vec::new(),
+            messages: Vec::new(),
+            visible: Vec::new(),
+            seed: 0,
+            rng: GameRng::seed_from_u64(0),
+            depth: 0,
+            floors: (0..dungeon::FLOORS).map(|_| None).collect(),
+            room_templates: Vec::new(),
             quit: false,
-            position: glam::u16vec2(1, 1),
             level,
             number: "".to_string(),
             tiles,
+            entity_types,
+            entities: Vec::new(),
+            item_types,
+            action_costs,
+            floor_items: Vec::new(),
+            inventory: Vec::new(),
+            held: None,
+            inventory_menu: None,
+            resume_menu: None,
+            editor: None,
             ui: Vec::new(),
             name: "".to_string(),
             text_input: false,
+        };
+        state
+            .spawn("player", glam::u16vec2(1, 1))
+            .ok_or_else(|| anyhow::anyhow!("res/entity.toml has no [player] entry"))?;
+        Ok(state)
+    }
+
+    pub fn player(&self) -> &Entity {
+        &self.entities[PLAYER]
+    }
+
+    pub fn skill(&self, skill: Skill) -> u16 {
+        let level = self.skills.level(skill, &self.nutrition);
+        if skill.nutrient() == Nutrient::Protein && self.has(Deficiency::Weakness) {
+            level / 2
+        } else {
+            level
         }
     }
 
+    pub fn has(&self, deficiency: Deficiency) -> bool {
+        self.deficiencies.contains(&deficiency)
+    }
+
+    pub fn sight_radius(&self) -> u16 {
+        let radius = 4 + self.skill(Skill::Tracking);
+        if self.has(Deficiency::Blindness) {
+            radius / 3
+        } else {
+            radius
+        }
+    }
+
+    pub fn message<S: Into<String>>(&mut self, text: S) {
+        self.messages.push(text.into());
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+    }
+
+    pub fn spawn(&mut self, kind: &str, position: glam::U16Vec2) -> Option<usize> {
+        let entity = self.entity_types.get(kind)?.spawn(kind, position);
+        self.entities.push(entity);
+        Some(self.entities.len() - 1)
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = GameRng::seed_from_u64(seed);
+    }
+
+    // generate the floor at self.depth, replacing the current one
+    pub fn generate_floor(&mut self) {
+        let mut rng = GameRng::seed_from_u64(dungeon::floor_seed(self.seed, self.depth));
+        self.level = Level::new(LEVEL_SIZE);
+        self.level
+            .generate(&self.tiles, &self.room_templates, self.depth, &mut rng);
+        self.entities.truncate(1);
+        self.floor_items.clear();
+
+        let mut floor = self.level.floor_positions(&self.tiles);
+        floor.shuffle(&mut rng);
+        let mut stairs = Vec::new();
+        if self.depth > 0 {
+            stairs.push("stairs_up");
+        }
+        if self.depth + 1 < dungeon::FLOORS {
+            stairs.push("stairs_down");
+        }
+        for name in stairs {
+            if let (Some(position), Some(tile)) = (floor.pop(), self.tiles.get_index_of(name)) {
+                self.level.data[position.y as usize][position.x as usize] = tile;
+            }
+        }
+
+        self.populate(
+            &mut floor,
+            dungeon::monsters(self.depth),
+            dungeon::items(self.depth),
+            &mut rng,
+        );
+        if let Some(position) = floor.pop() {
+            self.entities[PLAYER].position = position;
+        }
+    }
+
+    // place monsters and items on random free floor tiles
+    fn populate(
+        &mut self,
+        floor: &mut Vec<glam::U16Vec2>,
+        monsters: usize,
+        items: usize,
+        rng: &mut GameRng,
+    ) {
+        let kinds: Vec<String> = self
+            .entity_types
+            .keys()
+            .filter(|kind| *kind != "player")
+            .cloned()
+            .collect();
+        for _ in 0..monsters {
+            let (Some(position), Some(kind)) = (floor.pop(), kinds.choose(rng)) else {
+                break;
+            };
+            self.spawn(kind, position);
+        }
+        // with no item types there is nothing to pick from
+        if self.item_types.is_empty() {
+            return;
+        }
+        for _ in 0..items {
+            let Some(position) = floor.pop() else {
+                break;
+            };
+            let item = rng.gen_range(0..self.item_types.len());
+            self.floor_items.push((position, item));
+        }
+    }
+
+    // stash the current floor and move the player to another one, generating it on the first visit
+    fn change_floor(&mut self, depth: usize) {
+        let current = Floor {
+            level: std::mem::replace(&mut self.level, Level::new(glam::U16Vec2::ZERO)),
+            entities: self.entities.drain(1..).collect(),
+            floor_items: std::mem::take(&mut self.floor_items),
+        };
+        let going_down = depth > self.depth;
+        self.floors[self.depth] = Some(current);
+        self.depth = depth;
+
+        match self.floors[depth].take() {
+            Some(floor) => {
+                self.level = floor.level;
+                self.entities.extend(floor.entities);
+                self.floor_items = floor.floor_items;
+            }
+            None => self.generate_floor(),
+        }
+
+        // arrive on the stairs leading back where we came from
+        let arrival = self.tiles.get_index_of(if going_down {
+            "stairs_up"
+        } else {
+            "stairs_down"
+        });
+        if let Some(position) = self.find_tile(arrival) {
+            // anything standing on the stairs makes way for the player
+            self.entities
+                .retain(|x| x.position != position || x.kind == "player");
+            self.entities[PLAYER].position = position;
+        }
+        self.message(format!("You reach depth {}.", self.depth + 1));
+    }
+
+    fn find_tile(&self, tile: Option<usize>) -> Option<glam::U16Vec2> {
+        let tile = tile?;
+        self.level.data.iter().enumerate().find_map(|(i, row)| {
+            row.iter()
+                .position(|x| *x == tile)
+                .map(|j| glam::u16vec2(j as u16, i as u16))
+        })
+    }
+
+    fn use_stairs(&mut self, down: bool) {
+        let position = self.player().position;
+        let tile = self.level.data[position.y as usize][position.x as usize];
+        if down && Some(tile) == self.tiles.get_index_of("stairs_down") {
+            self.change_floor(self.depth + 1);
+        } else if !down && Some(tile) == self.tiles.get_index_of("stairs_up") {
+            self.change_floor(self.depth - 1);
+        } else {
+            self.message("There are no stairs here.");
+        }
+    }
+
+    // takes the terminal size, the display is what is left after the status lines
     pub fn resize(&mut self, size: glam::U16Vec2) {
-        self.display.size = size;
+        self.display = Display::new(display_size(size));
+        if let Some(menu) = &self.inventory_menu {
+            self.inventory_menu = Some(self.make_inventory_menu(menu.selection));
+        }
+        if let Some(menu) = &self.resume_menu {
+            let selection = menu.selection;
+            let mut menu = self.make_resume_menu();
+            menu.selection = selection;
+            self.resume_menu = Some(menu);
+        }
     }
 
     pub fn update(&mut self) {
-        if self.hunger == 0 {
+        if self.nutrition.starving() {
             self.quit = true;
             return;
         }
@@ -69,6 +314,12 @@ impl GameState {
                 TextInput::Exit => self.text_input = false,
                 TextInput::None => {}
             }
+        } else if self.editor.is_some() {
+            self.editor_input(self.inputs.get_input());
+        } else if self.resume_menu.is_some() {
+            self.resume_input(self.inputs.get_input());
+        } else if self.inventory_menu.is_some() {
+            self.inventory_input(self.inputs.get_input());
         } else {
             match self.inputs.get_input() {
                 Input::Quit => self.quit = true,
@@ -90,22 +341,52 @@ impl GameState {
                 Input::Number('8') => self.number.push('8'),
                 Input::Number('9') => self.number.push('9'),
                 Input::Number('0') => self.number.push('0'),
-                Input::MenuPrev => self.ui[0].prev(),
-                Input::MenuNext => self.ui[0].next(),
-                Input::Select => {
-                    self.level.data[self.position.y as usize][self.position.x as usize] =
-                        self.ui[0].selection
+                Input::MenuPrev => {
+                    if let Some(menu) = self.ui.first_mut() {
+                        menu.prev()
+                    }
+                }
+                Input::MenuNext => {
+                    if let Some(menu) = self.ui.first_mut() {
+                        menu.next()
+                    }
                 }
                 Input::EnterText => self.text_input = true,
+                Input::Inventory => self.inventory_menu = Some(self.make_inventory_menu(0)),
+                Input::Pickup => self.pickup(),
+                Input::Wait => self.player_act(Action::Wait),
+                Input::StairsDown => self.use_stairs(true),
+                Input::StairsUp => self.use_stairs(false),
+                Input::Save => match self.save(save::SAVE_PATH) {
+                    Ok(()) => self.message("Game saved."),
+                    Err(error) => self.message(format!("Could not save: {error}")),
+                },
+                Input::Load => self.load_game(),
+                Input::Screenshot => self.screenshot(),
+                Input::Resize(x, y) => self.resize(glam::u16vec2(x, y)),
+                Input::Run(x, y) => self.run(glam::i16vec2(x, y)),
+                Input::Click(x, y) | Input::RightClick(x, y) => self.click(glam::u16vec2(x, y)),
+                Input::Eat => {
+                    if let Some(slot) = self.held {
+                        self.eat(slot);
+                    }
+                }
                 _ => {}
             }
         }
 
+        if self.editor.is_some() {
+            self.draw_editor();
+            self.draw_name();
+            return;
+        }
+
+        self.update_deficiencies();
+        self.update_fov();
+
         // put level on display
-        let diff = (self.display.size / 2).as_i16vec2();
-        let ipos = self.position.as_i16vec2();
-        let start = glam::i16vec2(ipos.x - diff.x, ipos.y - diff.y);
-        let end = glam::i16vec2(ipos.x + diff.x, ipos.y + diff.y);
+        let start = self.camera();
+        let end = start + (self.display.size / 2 * 2).as_i16vec2();
 
         for (display_i, level_i) in (start.y..end.y).enumerate() {
             for (display_j, level_j) in (start.x..end.x).enumerate() {
@@ -116,57 +397,531 @@ impl GameState {
                 {
                     self.display.data[display_i][display_j] = Tile::new(' ', 0, 0, false);
                 } else {
-                    let tile = self.level.data[level_i as usize][level_j as usize];
-                    let tile = self.tiles[tile];
-                    if level_i == self.position.y as i16 && level_j == self.position.x as i16 {
-                        self.display.data[display_i][display_j] = Tile {
-                            r#char: '@',
-                            fore: 15,
-                            back: tile.back,
-                            r#move: true,
-                        };
+                    let (i, j) = (level_i as usize, level_j as usize);
+                    let tile = self.tiles[self.level.data[i][j]];
+                    self.display.data[display_i][display_j] = if self.visible[i][j] {
+                        tile
+                    } else if self.level.explored[i][j] {
+                        tile.dimmed()
                     } else {
-                        self.display.data[display_i][display_j] = tile;
-                    }
+                        Tile::new(' ', 0, 0, false)
+                    };
                 }
             }
         }
 
+        // put items on display
+        for (position, item) in self.floor_items.iter() {
+            let pos = position.as_i16vec2() - start;
+            if pos.x < 0
+                || pos.y < 0
+                || pos.x >= end.x - start.x
+                || pos.y >= end.y - start.y
+                || !self.in_sight(*position)
+            {
+                continue;
+            }
+            let item = &self.item_types[*item];
+            let cell = &mut self.display.data[pos.y as usize][pos.x as usize];
+            *cell = Tile::new(item.char, item.fore, cell.back, true);
+        }
+
+        // put entities on display, player last so it is always on top
+        for entity in self
+            .entities
+            .iter()
+            .skip(1)
+            .chain(self.entities.iter().take(1))
+        {
+            let pos = entity.position.as_i16vec2() - start;
+            if pos.x < 0
+                || pos.y < 0
+                || pos.x >= end.x - start.x
+                || pos.y >= end.y - start.y
+                || !self.in_sight(entity.position)
+            {
+                continue;
+            }
+            let cell = &mut self.display.data[pos.y as usize][pos.x as usize];
+            *cell = Tile {
+                r#char: entity.char,
+                fore: entity.fore,
+                back: entity.back.unwrap_or(cell.back),
+                r#move: true,
+                opaque: false,
+            };
+        }
+
+        if self.has(Deficiency::Nausea) {
+            self.blur();
+        }
+
         // put ui elements on display
         for item in self.ui.iter() {
             item.render_to(&mut self.display);
         }
+        if let Some(menu) = &self.inventory_menu {
+            menu.render_to(&mut self.display);
+        }
+        if let Some(menu) = &self.resume_menu {
+            menu.render_to(&mut self.display);
+        }
+
+        self.draw_name();
+    }
 
+    fn draw_name(&mut self) {
         let text = Tile::from_string(&self.name, Some(15), Some(0));
-        for i in 0..self.display.size.y {
-            if i as usize == text.len() {
-                break;
+        if let Some(row) = self.display.data.first_mut() {
+            for (cell, tile) in row.iter_mut().zip(text) {
+                *cell = tile;
             }
-            self.display.data[0][i as usize] = text[i as usize];
         }
     }
 
     fn try_move(&mut self, delta: glam::I16Vec2) {
         for _i in 1..=self.number() as i16 {
-            let new_y = self.position.y as i16 + delta.y;
-            let new_x = self.position.x as i16 + delta.x;
-            tracing::info!("{new_y}, {new_x}");
-
-            if self.hunger == 0
-                || new_y < 0
-                || new_y >= self.level.size.y as i16
-                || new_x < 0
-                || new_x >= self.level.size.x as i16
-            // !self.tiles[self.level.data[new_y as usize][new_x as usize]].r#move
-            // TODO: fix this
-            {
+            if self.nutrition.starving() {
+                break;
+            }
+            match self.step(PLAYER, delta) {
+                MoveOutcome::Moved => self.player_act(Action::Move),
+                MoveOutcome::Wall => break,
+                MoveOutcome::Creature(target) => {
+                    self.attack(PLAYER, target);
+                    self.player_act(Action::Attack);
+                    break;
+                }
+                MoveOutcome::Door(position) => {
+                    self.open_door(position);
+                    self.player_act(Action::Move);
+                    break;
+                }
+            }
+        }
+    }
+
+    // keep moving until something is in the way or there is something new to look at
+    fn run(&mut self, delta: glam::I16Vec2) {
+        for _ in 0..RUN_LIMIT {
+            if self.nutrition.starving() || self.quit {
+                break;
+            }
+            match self.step(PLAYER, delta) {
+                MoveOutcome::Moved => self.player_act(Action::Move),
+                _ => break,
+            }
+            self.update_fov();
+            let position = self.player().position;
+            let monster_seen = self
+                .entities
+                .iter()
+                .skip(1)
+                .any(|x| self.in_sight(x.position));
+            if monster_seen || self.floor_items.iter().any(|(x, _)| *x == position) {
                 break;
             }
-            self.position = (self.position.as_i16vec2() + delta).as_u16vec2();
-            self.hunger -= 1;
         }
     }
 
+    // the level position drawn at the top left of the display
+    pub fn camera(&self) -> glam::I16Vec2 {
+        self.player().position.as_i16vec2() - (self.display.size / 2).as_i16vec2()
+    }
+
+    fn display_to_level(&self, position: glam::U16Vec2) -> Option<glam::U16Vec2> {
+        let position = self.camera() + position.as_i16vec2();
+        (position.x >= 0
+            && position.y >= 0
+            && position.x < self.level.size.x as i16
+            && position.y < self.level.size.y as i16)
+            .then_some(position.as_u16vec2())
+    }
+
+    // picks a menu item or describes what is in the clicked cell
+    fn click(&mut self, position: glam::U16Vec2) {
+        let Some(position) = screen_to_display(self.display.size, position) else {
+            return;
+        };
+        if let Some(menu) = self.ui.first_mut() {
+            if let Some(item) = menu.item_at(position) {
+                menu.selection = item;
+                return;
+            }
+        }
+        let Some(cell) = self.display_to_level(position) else {
+            return;
+        };
+        self.describe(cell);
+    }
+
+    fn describe(&mut self, position: glam::U16Vec2) {
+        if !self.level.explored[position.y as usize][position.x as usize] {
+            self.message("You have not seen that place.");
+            return;
+        }
+        let mut names = Vec::new();
+        if self.in_sight(position) {
+            if let Some(index) = self.entity_at(position) {
+                names.push(self.entities[index].kind.clone());
+            }
+            for (_, item) in self.floor_items.iter().filter(|(x, _)| *x == position) {
+                names.push(self.item_types.get_index(*item).unwrap().0.clone());
+            }
+        }
+        let tile = self.level.data[position.y as usize][position.x as usize];
+        names.push(self.tiles.get_index(tile).unwrap().0.clone());
+        self.message(format!("You see: {}.", names.join(", ")));
+    }
+
+    pub fn step(&mut self, index: usize, delta: glam::I16Vec2) -> MoveOutcome {
+        let position = self.entities[index].position.as_i16vec2() + delta;
+        tracing::info!("{index}: {position}");
+
+        if position.y < 0
+            || position.y >= self.level.size.y as i16
+            || position.x < 0
+            || position.x >= self.level.size.x as i16
+        {
+            return MoveOutcome::Wall;
+        }
+        let position = position.as_u16vec2();
+        if let Some(target) = self.entity_at(position) {
+            return MoveOutcome::Creature(target);
+        }
+        let tile = self.level.data[position.y as usize][position.x as usize];
+        if Some(tile) == self.tiles.get_index_of("door") {
+            return MoveOutcome::Door(position);
+        }
+        if !self.tiles[tile].r#move {
+            return MoveOutcome::Wall;
+        }
+        self.entities[index].position = position;
+        MoveOutcome::Moved
+    }
+
+    pub fn entity_at(&self, position: glam::U16Vec2) -> Option<usize> {
+        self.entities.iter().position(|x| x.position == position)
+    }
+
+    fn open_door(&mut self, position: glam::U16Vec2) {
+        if let Some(open) = self.tiles.get_index_of("door_open") {
+            self.level.data[position.y as usize][position.x as usize] = open;
+        }
+    }
+
+    fn attack(&mut self, attacker: usize, target: usize) {
+        let mut damage = self.entities[attacker].attack;
+        if attacker == PLAYER {
+            damage += self.skill(Skill::Slaughter) / 2;
+            if let Some(held) = self.held {
+                damage += self.item_types[self.inventory[held]].attack;
+            }
+        }
+
+        let target_entity = &mut self.entities[target];
+        target_entity.health = target_entity.health.saturating_sub(damage);
+        let kind = target_entity.kind.clone();
+        let dead = target_entity.health == 0;
+
+        if target == PLAYER {
+            let attacker_kind = self.entities[attacker].kind.clone();
+            self.message(format!("The {attacker_kind} hits you for {damage}."));
+            if dead {
+                self.message("You die.");
+                self.quit = true;
+            }
+        } else if dead {
+            self.entities.remove(target);
+            self.message(format!("You kill the {kind}."));
+        } else {
+            self.message(format!("You hit the {kind} for {damage}."));
+        }
+    }
+
+    pub fn action_cost(&self, index: usize, action: Action) -> i32 {
+        let mut cost = turn::base_cost(&self.action_costs, action);
+        if index == PLAYER {
+            if action == Action::Move {
+                cost = cost * 10 / (6 + self.skill(Skill::Athletics) as i32);
+            }
+            if self.has(Deficiency::Fatigue) {
+                cost *= 2;
+            }
+        }
+        // a free action would let the tick loop run forever
+        cost.max(1)
+    }
+
+    fn spend(&mut self, index: usize, action: Action) {
+        self.entities[index].energy -= self.action_cost(index, action);
+    }
+
+    // the player acts, then everything else gets its turns until the player can act again
+    fn player_act(&mut self, action: Action) {
+        self.spend(PLAYER, action);
+        while self.player().energy < 0 && !self.nutrition.starving() {
+            for entity in self.entities.iter_mut() {
+                entity.energy += entity.speed.max(1);
+            }
+            self.nutrition.burn(1.0 / turn::TICKS_PER_TURN);
+            for index in 1..self.entities.len() {
+                while self.entities[index].energy >= 0 {
+                    self.monster_act(index);
+                }
+            }
+        }
+    }
+
+    fn monster_act(&mut self, index: usize) {
+        let delta =
+            self.player().position.as_i16vec2() - self.entities[index].position.as_i16vec2();
+        let delta = if delta.abs().max_element() <= MONSTER_SIGHT {
+            delta.signum()
+        } else {
+            glam::i16vec2(self.rng.gen_range(-1..=1), self.rng.gen_range(-1..=1))
+        };
+        if delta == glam::I16Vec2::ZERO {
+            self.spend(index, Action::Wait);
+            return;
+        }
+        match self.step(index, delta) {
+            MoveOutcome::Creature(PLAYER) => {
+                self.attack(index, PLAYER);
+                self.spend(index, Action::Attack);
+            }
+            MoveOutcome::Door(position) => {
+                self.open_door(position);
+                self.spend(index, Action::Move);
+            }
+            _ => self.spend(index, Action::Move),
+        }
+    }
+
+    fn in_sight(&self, position: glam::U16Vec2) -> bool {
+        self.visible[position.y as usize][position.x as usize]
+    }
+
+    fn update_fov(&mut self) {
+        let (level, tiles) = (&self.level, &self.tiles);
+        self.visible = fov::compute(
+            level.size,
+            self.entities[PLAYER].position,
+            self.sight_radius(),
+            |x, y| tiles[level.data[y][x]].opaque,
+        );
+        for (explored, visible) in self.level.explored.iter_mut().zip(self.visible.iter()) {
+            for (explored, visible) in explored.iter_mut().zip(visible.iter()) {
+                *explored |= *visible;
+            }
+        }
+    }
+
+    fn update_deficiencies(&mut self) {
+        let current = self.nutrition.deficiencies();
+        for deficiency in current.iter() {
+            if !self.has(*deficiency) {
+                self.message(deficiency.onset_message());
+            }
+        }
+        for deficiency in self.deficiencies.clone() {
+            if !current.contains(&deficiency) {
+                self.message(deficiency.recovery_message());
+            }
+        }
+        self.deficiencies = current;
+    }
+
+    // swap random glyphs with their neighbours
+    fn blur(&mut self) {
+        let size = self.display.size;
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+        for _ in 0..(size.x as usize * size.y as usize / 16) {
+            let a = glam::u16vec2(
+                rand::random::<u16>() % size.x,
+                rand::random::<u16>() % size.y,
+            );
+            let b = glam::u16vec2(
+                (a.x + 1).min(size.x - 1),
+                (a.y + rand::random::<u16>() % 2).min(size.y - 1),
+            );
+            let c = self.display[a].char;
+            self.display[a].char = self.display[b].char;
+            self.display[b].char = c;
+        }
+    }
+
+    fn inventory_input(&mut self, input: Input) {
+        let Some(menu) = self.inventory_menu.as_mut() else {
+            return;
+        };
+        match input {
+            Input::Quit | Input::Inventory => self.inventory_menu = None,
+            Input::Resize(x, y) => self.resize(glam::u16vec2(x, y)),
+            Input::Click(x, y) => {
+                let clicked = screen_to_display(self.display.size, glam::u16vec2(x, y))
+                    .and_then(|position| menu.item_at(position));
+                if let Some(slot) = clicked {
+                    self.held = Some(slot);
+                    self.inventory_menu = None;
+                }
+            }
+            Input::Up | Input::MenuPrev => menu.prev(),
+            Input::Down | Input::MenuNext => menu.next(),
+            Input::Select => {
+                if menu.selection < self.inventory.len() {
+                    self.held = Some(menu.selection);
+                }
+                self.inventory_menu = None;
+            }
+            Input::Drop => {
+                let selection = menu.selection;
+                self.drop(selection);
+                self.inventory_menu = Some(self.make_inventory_menu(selection));
+            }
+            Input::Eat => {
+                let selection = menu.selection;
+                self.eat(selection);
+                self.inventory_menu = Some(self.make_inventory_menu(selection));
+            }
+            _ => {}
+        }
+    }
+
+    fn resume_input(&mut self, input: Input) {
+        let Some(menu) = self.resume_menu.as_mut() else {
+            return;
+        };
+        match input {
+            Input::Up | Input::MenuPrev => menu.prev(),
+            Input::Down | Input::MenuNext => menu.next(),
+            Input::Select => {
+                let resume = menu.selection == 0;
+                self.resume_menu = None;
+                if resume {
+                    self.load_game();
+                }
+            }
+            Input::Quit => self.resume_menu = None,
+            Input::Resize(x, y) => self.resize(glam::u16vec2(x, y)),
+            Input::Click(x, y) => {
+                let clicked = screen_to_display(self.display.size, glam::u16vec2(x, y))
+                    .and_then(|position| menu.item_at(position));
+                if let Some(item) = clicked {
+                    self.resume_menu = None;
+                    if item == 0 {
+                        self.load_game();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn make_resume_menu(&self) -> Menu {
+        let items = ["Resume saved game", "New game"]
+            .into_iter()
+            .map(|x| Tile::from_string(x, Some(15), Some(0)))
+            .collect();
+        let size = glam::u16vec2(24, 3);
+        let position = (self.display.size.saturating_sub(size)) / 2;
+        Menu::new("Resume?", position, size, items)
+    }
+
+    // the last drawn display in every export format
+    fn screenshot(&mut self) {
+        for format in export::Format::ALL {
+            let path = format!("screenshot.{}", format.extension());
+            if let Err(error) = export::write(&path, &self.display.data, format) {
+                self.message(format!("Could not write {path}: {error}"));
+                return;
+            }
+        }
+        self.message("Saved screenshot.ans, screenshot.html and screenshot.txt.");
+    }
+
+    fn load_game(&mut self) {
+        match self.load(save::SAVE_PATH) {
+            Ok(()) => self.message("Game loaded."),
+            Err(error) => self.message(format!("Could not load: {error}")),
+        }
+    }
+
+    fn make_inventory_menu(&self, selection: usize) -> Menu {
+        let items = self
+            .inventory
+            .iter()
+            .enumerate()
+            .map(|(slot, index)| {
+                let (name, item) = self.item_types.get_index(*index).unwrap();
+                let mut vec = vec![
+                    Tile::new(item.char, item.fore, 0, false),
+                    Tile::new(' ', 0, 0, false),
+                ];
+                vec.extend(Tile::from_string(name, Some(15), Some(0)));
+                if self.held == Some(slot) {
+                    vec.extend(Tile::from_string(" (held)", Some(245), Some(0)));
+                }
+                vec
+            })
+            .collect();
+
+        let size = glam::u16vec2(30, 15);
+        let position = glam::u16vec2(self.display.size.x.saturating_sub(size.x + 2), 1);
+        let mut menu = Menu::new("Inventory", position, size, items);
+        menu.selection = selection.min(self.inventory.len().saturating_sub(1));
+        menu
+    }
+
+    fn pickup(&mut self) {
+        let position = self.player().position;
+        if let Some(index) = self
+            .floor_items
+            .iter()
+            .rposition(|(pos, _)| *pos == position)
+        {
+            let (_, item) = self.floor_items.remove(index);
+            self.inventory.push(item);
+            self.player_act(Action::Pickup);
+        }
+    }
+
+    fn drop(&mut self, slot: usize) {
+        if let Some(item) = self.take(slot) {
+            self.floor_items.push((self.player().position, item));
+            self.player_act(Action::Drop);
+        }
+    }
+
+    fn eat(&mut self, slot: usize) {
+        let Some(food) = self
+            .inventory
+            .get(slot)
+            .and_then(|item| self.item_types[*item].food)
+        else {
+            return;
+        };
+        self.take(slot);
+        self.nutrition.eat(&food);
+        self.player_act(Action::Eat);
+    }
+
+    // remove an item from the inventory, keeping `held` pointing at the same item
+    fn take(&mut self, slot: usize) -> Option<usize> {
+        if slot >= self.inventory.len() {
+            return None;
+        }
+        let item = self.inventory.remove(slot);
+        self.held = match self.held {
+            Some(held) if held == slot => None,
+            Some(held) if held > slot => Some(held - 1),
+            held => held,
+        };
+        Some(item)
+    }
+
     fn number(&mut self) -> u16 {
         let result = match self.number.parse() {
             Err(_) => 1,
@@ -176,3 +931,12 @@ impl GameState {
         result
     }
 }
+
+fn display_size(size: glam::U16Vec2) -> glam::U16Vec2 {
+    glam::u16vec2(size.x, size.y.saturating_sub(HUD_ROWS))
+}
+
+pub fn screen_to_display(size: glam::U16Vec2, position: glam::U16Vec2) -> Option<glam::U16Vec2> {
+    let position = glam::u16vec2(position.x, position.y.checked_sub(STATUS_ROWS)?);
+    (position.x < size.x && position.y < size.y).then_some(position)
+}
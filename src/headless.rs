@@ -0,0 +1,156 @@
+use crate::game::GameState;
+use crate::renderer::{Display, Renderer};
+use crate::tile::Tile;
+
+// renders into memory instead of a terminal, so the screen can be inspected without a tty
+pub struct Headless {
+    pub size: glam::U16Vec2,
+    pub frame: Display, // the whole screen, status lines included
+    pub frames: usize,
+    cursor: glam::U16Vec2,
+}
+
+impl Headless {
+    pub fn new(size: glam::U16Vec2) -> Self {
+        Self {
+            size,
+            frame: Display::new(size),
+            frames: 0,
+            cursor: glam::U16Vec2::ZERO,
+        }
+    }
+
+    // the last frame as plain text, one line per row with trailing spaces trimmed
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for row in self.frame.data.iter() {
+            let line: String = row.iter().map(|tile| tile.char).collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    fn print(&mut self, position: glam::U16Vec2, text: &str) {
+        self.cursor = position;
+        for c in text.chars() {
+            self.tile(&Tile::new(c, 15, 0, true)).unwrap();
+        }
+    }
+}
+
+impl Renderer for Headless {
+    fn init(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn quit(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn render(&mut self, state: &GameState) -> anyhow::Result<()> {
+        self.frame = Display::new(self.size);
+
+        let status = format!(
+            "HLTH: {} HUNG: {}%",
+            state.player().health,
+            (state.nutrition.calorie_fraction() * 100.0) as u16
+        );
+        self.print(glam::u16vec2(0, 0), &status);
+        if let Some(message) = state.messages.last() {
+            self.print(glam::u16vec2(0, 1), message);
+        }
+        let seed = format!("DEPTH: {} SEED: {}", state.depth + 1, state.seed);
+        self.print(
+            glam::u16vec2(self.size.x.saturating_sub(seed.len() as u16), 1),
+            &seed,
+        );
+
+        for i in 0..state.display.size.y.min(self.size.y.saturating_sub(2)) {
+            self.cursor = glam::u16vec2(0, i + 2);
+            for j in 0..state.display.size.x {
+                self.tile(&state.display.data[i as usize][j as usize])?;
+            }
+        }
+
+        self.frames += 1;
+        Ok(())
+    }
+
+    // writes at the cursor and advances it, anything off screen is dropped
+    fn tile(&mut self, tile: &Tile) -> anyhow::Result<()> {
+        if self.cursor.x < self.size.x && self.cursor.y < self.size.y {
+            self.frame[self.cursor] = *tile;
+        }
+        self.cursor.x += 1;
+        Ok(())
+    }
+
+    fn resize(&self) -> anyhow::Result<glam::U16Vec2> {
+        Ok(self.size)
+    }
+
+    // tiles are copied straight out of the display, there is no tileset to look up
+    fn get_tile_index(&self, _name: &str) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Input;
+    use crate::replay::{Event, Script};
+    use crate::{level, tile, util};
+
+    // plays a script to the end on a fresh game, the same way main runs one
+    fn play(seed: u64, inputs: &[Input]) -> Headless {
+        let mut headless = Headless::new(glam::u16vec2(80, 24));
+        let script = Script::new(inputs.iter().map(|x| Event::Input(*x)).collect());
+        let mut state = GameState::init(
+            &headless,
+            Box::new(script),
+            level::Level::new(level::LEVEL_SIZE),
+            tile::import_tiles("res/tiles.toml").unwrap(),
+            util::import_toml("res/entity.toml").unwrap(),
+            util::import_toml("res/items.toml").unwrap(),
+            util::import_toml("res/actions.toml").unwrap(),
+        )
+        .unwrap();
+        state.reseed(seed);
+        state.room_templates = level::import_rooms().unwrap();
+        state.generate_floor();
+        loop {
+            state.update();
+            if state.quit {
+                break;
+            }
+            headless.render(&state).unwrap();
+        }
+        headless
+    }
+
+    #[test]
+    fn shows_status_and_player() {
+        let headless = play(7, &[Input::Wait, Input::Wait]);
+        let text = headless.text();
+        assert_eq!(headless.frames, 2);
+        assert!(text.lines().next().unwrap().starts_with("HLTH: 160 HUNG: "));
+        assert!(text.lines().nth(1).unwrap().ends_with("DEPTH: 1 SEED: 7"));
+        assert!(text.lines().skip(2).any(|line| line.contains('@')));
+    }
+
+    // the player starts well fed, so nothing is blurred and frames can be compared
+    #[test]
+    fn same_script_same_screen() {
+        let inputs = [
+            Input::Up,
+            Input::Left,
+            Input::Wait,
+            Input::Down,
+            Input::Right,
+        ];
+        assert_eq!(play(3, &inputs).text(), play(3, &inputs).text());
+        assert_ne!(play(3, &inputs).text(), play(4, &inputs).text());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::level::Level;
+use crate::tile::Tile;
+use crate::util::parse_toml;
+use anyhow::Context;
+use indexmap::IndexMap;
+use std::io::Read;
+
+pub const LEGEND_PATH: &str = "res/legend.toml";
+
+pub struct Image {
+    pub size: glam::U16Vec2,
+    pub pixels: Vec<[u8; 3]>, // row by row
+}
+
+// png by extension, anything else is read as a ppm
+pub fn load(path: &str) -> anyhow::Result<Image> {
+    let image = if path.to_lowercase().ends_with(".png") {
+        load_png(path)
+    } else {
+        load_ppm(path)
+    };
+    image.with_context(|| format!("could not read image {path}"))
+}
+
+fn load_png(path: &str) -> anyhow::Result<Image> {
+    let mut decoder = png::Decoder::new(std::fs::File::open(path)?);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let channels = info.color_type.samples();
+    let pixels = buffer[..info.buffer_size()]
+        .chunks(channels)
+        .map(|x| match info.color_type {
+            png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => [x[0]; 3],
+            _ => [x[0], x[1], x[2]],
+        })
+        .collect();
+    Ok(Image {
+        size: glam::u16vec2(info.width as u16, info.height as u16),
+        pixels,
+    })
+}
+
+// plain (P3) and raw (P6) ppm with up to 8 bits per channel
+fn load_ppm(path: &str) -> anyhow::Result<Image> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)?.read_to_end(&mut bytes)?;
+    parse_ppm(&bytes)
+}
+
+fn parse_ppm(bytes: &[u8]) -> anyhow::Result<Image> {
+    // header fields are separated by whitespace and may have comments between them
+    let mut position = 0;
+    let mut next_token = |bytes: &[u8]| -> anyhow::Result<String> {
+        loop {
+            match bytes.get(position) {
+                Some(b'#') => {
+                    while bytes.get(position).is_some_and(|x| *x != b'\n') {
+                        position += 1;
+                    }
+                }
+                Some(x) if x.is_ascii_whitespace() => position += 1,
+                Some(_) => break,
+                None => anyhow::bail!("unexpected end of file"),
+            }
+        }
+        let start = position;
+        while bytes
+            .get(position)
+            .is_some_and(|x| !x.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        Ok(String::from_utf8_lossy(&bytes[start..position]).into_owned())
+    };
+
+    let magic = next_token(bytes)?;
+    let width: u16 = next_token(bytes)?.parse().context("bad width")?;
+    let height: u16 = next_token(bytes)?.parse().context("bad height")?;
+    let max: u16 = next_token(bytes)?.parse().context("bad maximum value")?;
+    if max == 0 || max > 255 {
+        anyhow::bail!("maximum value {max} is not supported");
+    }
+    let count = width as usize * height as usize * 3;
+    let samples: Vec<u16> = match magic.as_str() {
+        "P3" => (0..count)
+            .map(|_| Ok(next_token(bytes)?.parse()?))
+            .collect::<anyhow::Result<_>>()?,
+        // a single whitespace byte separates the header from the pixels
+        "P6" => bytes
+            .get(position + 1..position + 1 + count)
+            .context("unexpected end of file")?
+            .iter()
+            .map(|x| *x as u16)
+            .collect(),
+        _ => anyhow::bail!("not a ppm image"),
+    };
+    if let Some(sample) = samples.iter().find(|x| **x > max) {
+        anyhow::bail!("sample {sample} is above the maximum value {max}");
+    }
+    let pixels = samples
+        .chunks(3)
+        .map(|x| [0, 1, 2].map(|i| (x[i] as u32 * 255 / max as u32) as u8))
+        .collect();
+    Ok(Image {
+        size: glam::u16vec2(width, height),
+        pixels,
+    })
+}
+
+pub fn import_legend(path: &str) -> anyhow::Result<IndexMap<String, [u8; 3]>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("could not read {path}"))?;
+    parse_toml(path, &text)
+}
+
+// each pixel becomes the tile its colour is given for in the legend
+pub fn to_level(
+    image: &Image,
+    legend: &IndexMap<String, [u8; 3]>,
+    tiles: &IndexMap<String, Tile>,
+) -> anyhow::Result<Level> {
+    let mut colours = Vec::new();
+    for (name, colour) in legend {
+        let tile = tiles
+            .get_index_of(name)
+            .with_context(|| format!("legend has unknown tile `{name}`"))?;
+        colours.push((*colour, tile));
+    }
+
+    let mut level = Level::new(image.size);
+    for (i, pixel) in image.pixels.iter().enumerate() {
+        let (x, y) = (i % image.size.x as usize, i / image.size.x as usize);
+        let (_, tile) = colours.iter().find(|(x, _)| x == pixel).with_context(|| {
+            let [r, g, b] = pixel;
+            format!("colour #{r:02x}{g:02x}{b:02x} at {x}, {y} is not in the legend")
+        })?;
+        level.data[y][x] = *tile;
+    }
+    Ok(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(bytes: &[u8]) -> String {
+        parse_ppm(bytes).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn plain_with_comment() {
+        let image =
+            parse_ppm(b"P3\n# made by hand\n2 1 # width and height\n255\n255 0 0  0 0 255\n")
+                .unwrap();
+        assert_eq!(image.size, glam::u16vec2(2, 1));
+        assert_eq!(image.pixels, vec![[255, 0, 0], [0, 0, 255]]);
+    }
+
+    #[test]
+    fn raw() {
+        let image = parse_ppm(b"P6 1 2 255\n\x01\x02\x03\x0a\x20\xff").unwrap();
+        assert_eq!(image.size, glam::u16vec2(1, 2));
+        assert_eq!(image.pixels, vec![[1, 2, 3], [10, 32, 255]]);
+    }
+
+    #[test]
+    fn scales_small_maximum() {
+        let image = parse_ppm(b"P3 1 1 15 15 5 0").unwrap();
+        assert_eq!(image.pixels, vec![[255, 85, 0]]);
+    }
+
+    #[test]
+    fn truncated_raw() {
+        assert_eq!(
+            error(b"P6 2 1 255\n\x01\x02\x03\x04"),
+            "unexpected end of file"
+        );
+    }
+
+    #[test]
+    fn sample_above_maximum() {
+        assert_eq!(
+            error(b"P3 1 1 15 15 0 300"),
+            "sample 300 is above the maximum value 15"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum Input {
     Up,
     Left,
@@ -9,6 +9,27 @@ pub enum Input {
     DownLeft,
     DownRight,
     Inventory,
+    Pickup,
+    Drop,
+    Eat,
+    Wait,
+    StairsDown,
+    StairsUp,
+    Save,
+    Load,
+    Screenshot,
+    Resize(u16, u16),
+    Run(i16, i16),
+    // screen coordinates
+    Click(u16, u16),
+    RightClick(u16, u16),
+    // editor
+    Brush,
+    Eyedropper,
+    Undo,
+    Redo,
+    RoomSize,
+    OpenRoom,
     None,
     Quit,
     Number(char),
@@ -18,6 +39,7 @@ pub enum Input {
     EnterText,
 }
 
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub enum TextInput {
     Char(char),
     Exit,
@@ -0,0 +1,12 @@
+use crate::nutrition::Nutrition;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct Item {
+    pub r#char: char,
+    pub fore: u8,
+    // bonus damage while held
+    #[serde(default)]
+    pub attack: u16,
+    #[serde(default)]
+    pub food: Option<Nutrition>,
+}
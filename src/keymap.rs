@@ -0,0 +1,137 @@
+use crate::input::Input;
+use crate::util::parse_toml;
+use anyhow::Context;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use indexmap::IndexMap;
+
+pub const KEYMAP_PATH: &str = "res/keymap.toml";
+
+// the layout used when there is no keymap file, built in so the two never drift apart
+const DEFAULT: &str = include_str!("../res/keymap.toml");
+
+pub struct Keymap {
+    bindings: Vec<(KeyCode, KeyModifiers, Input)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::parse(KEYMAP_PATH, DEFAULT).unwrap()
+    }
+}
+
+impl Keymap {
+    // falls back to the default layout when the file does not exist
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        if !std::path::Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        Self::parse(path, &std::fs::read_to_string(path)?)
+    }
+
+    fn parse(path: &str, text: &str) -> anyhow::Result<Self> {
+        let table: IndexMap<String, Input> = parse_toml(path, text)?;
+        let mut bindings = Vec::new();
+        for (key, input) in table {
+            let (code, modifiers) =
+                parse_key(&key).with_context(|| format!("unknown key `{key}` in {path}"))?;
+            bindings.push((code, modifiers, input));
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn get(&self, event: KeyEvent) -> Input {
+        let (code, modifiers) = normalise(event.code, event.modifiers);
+        self.bindings
+            .iter()
+            .find(|(c, m, _)| *c == code && *m == modifiers)
+            .map(|(_, _, input)| *input)
+            .unwrap_or(Input::None)
+    }
+}
+
+// keys are written like `h`, `S`, `ctrl+s`, `shift+left` or `f1`
+fn parse_key(key: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut parts: Vec<&str> = key.split('+').collect();
+    // a lone `+` splits into two empty strings
+    let name = if key.ends_with('+') {
+        parts.truncate(parts.len().saturating_sub(2));
+        "+"
+    } else {
+        parts.pop()?
+    };
+    for part in parts {
+        modifiers |= match part.to_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+    }
+
+    let mut chars = name.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match name.to_lowercase().as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" => KeyCode::Delete,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "begin" => KeyCode::KeypadBegin,
+            "space" => KeyCode::Char(' '),
+            f => KeyCode::F(f.strip_prefix('f')?.parse().ok()?),
+        },
+    };
+    Some(normalise(code, modifiers))
+}
+
+// shift is already part of a typed character, so `S` and `shift+s` are the same key
+fn normalise(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => (
+            KeyCode::Char(c.to_ascii_uppercase()),
+            modifiers - KeyModifiers::SHIFT,
+        ),
+        _ => (code, modifiers),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(keymap: &Keymap, code: KeyCode, modifiers: KeyModifiers) -> String {
+        format!("{:?}", keymap.get(KeyEvent::new(code, modifiers)))
+    }
+
+    #[test]
+    fn default_layout_parses() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.bindings.len(),
+            DEFAULT
+                .lines()
+                .filter(|x| !x.starts_with('#') && x.contains('='))
+                .count()
+        );
+        assert_eq!(
+            get(&keymap, KeyCode::Char('l'), KeyModifiers::CONTROL),
+            "Load"
+        );
+        assert_eq!(
+            get(&keymap, KeyCode::Char('l'), KeyModifiers::SHIFT),
+            "Run(1, 0)"
+        );
+        assert_eq!(get(&keymap, KeyCode::Up, KeyModifiers::SHIFT), "Run(0, -1)");
+    }
+}
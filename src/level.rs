@@ -1,13 +1,19 @@
 pub struct Level {
     pub size: glam::U16Vec2,
     pub data: Vec<Vec<usize>>,
+    pub explored: Vec<Vec<bool>>,
 }
 
 impl Level {
     pub fn new(size: glam::U16Vec2) -> Self {
         let data = vec![vec![0; size.x as usize].clone(); size.y as usize];
+        let explored = vec![vec![false; size.x as usize]; size.y as usize];
 
-        Self { size, data }
+        Self {
+            size,
+            data,
+            explored,
+        }
     }
 }
 
@@ -25,57 +31,250 @@ impl<'a, T: Clone> QuadtreeFlat<'a, T> {
     }
 }
 
-use crate::util::Quadtree;
+use crate::dungeon;
+use crate::tile::Tile;
+use crate::util::{import_toml, GameRng, Quadtree};
+use indexmap::IndexMap;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// two 64 x 64 quadtrees side by side, rooms share their border so one extra row and column
+pub const LEVEL_SIZE: glam::U16Vec2 = glam::U16Vec2::new(129, 65);
+pub const ROOM_SIZES: u8 = 5;
+
+// a room of size n spans (2 ^ n) + 1 tiles including its walls
+pub struct Room {
+    pub position: glam::U16Vec2,
+    pub size: u8,
+}
+
+impl Room {
+    pub fn span(&self) -> u16 {
+        (1 << self.size) + 1
+    }
+
+    fn end(&self) -> glam::U16Vec2 {
+        self.position + (self.span() - 1)
+    }
+
+    // middle of the wall shared with another room, if they touch along an edge
+    fn shared_wall(&self, other: &Room) -> Option<glam::U16Vec2> {
+        let (a0, a1) = (self.position, self.end());
+        let (b0, b1) = (other.position, other.end());
+        if a1.x == b0.x || b1.x == a0.x {
+            let x = if a1.x == b0.x { a1.x } else { a0.x };
+            let (lo, hi) = (a0.y.max(b0.y) + 1, a1.y.min(b1.y));
+            if lo < hi {
+                return Some(glam::u16vec2(x, (lo + hi - 1) / 2));
+            }
+        }
+        if a1.y == b0.y || b1.y == a0.y {
+            let y = if a1.y == b0.y { a1.y } else { a0.y };
+            let (lo, hi) = (a0.x.max(b0.x) + 1, a1.x.min(b1.x));
+            if lo < hi {
+                return Some(glam::u16vec2((lo + hi - 1) / 2, y));
+            }
+        }
+        None
+    }
+}
+
+// chance of keeping a door that is not needed to connect the level, adds loops
+const LOOP_CHANCE: f32 = 0.15;
+const DOOR_CHANCE: f32 = 0.5;
+
+pub fn import_rooms() -> anyhow::Result<Vec<IndexMap<String, RoomTile>>> {
+    (0..ROOM_SIZES)
+        .map(|size| import_toml::<RoomTile>(&format!("res/room_size_{size}.toml")))
+        .collect()
+}
 
 impl Level {
-    pub fn generate(&mut self, tile: usize) {
-        let mut level = [Quadtree::new(0), Quadtree::new(0)];
-
-        quadtree_gen(&mut level[0], 5);
-        quadtree_gen(&mut level[1], 5);
-
-        println!("{level:#?}");
-        let rooms = iter(&level[0], 0, 0);
-        let rooms2 = iter(&level[1], 0, 0);
-        for item in rooms.iter() {
-            let binary = format!("{:010b}", item.position);
-            let mut out = "".to_string();
-            for (i, c) in binary.chars().enumerate() {
-                if i % 2 == 0 && i != 0 {
-                    out.push(' ');
+    pub fn generate(
+        &mut self,
+        tiles: &IndexMap<String, Tile>,
+        templates: &[IndexMap<String, RoomTile>],
+        depth: usize,
+        rng: &mut GameRng,
+    ) -> Vec<Room> {
+        let wall = tiles.get_index_of("brick_wall").unwrap();
+        let floor = tiles.get_index_of("tile").unwrap();
+
+        let rooms = layout(dungeon::split_bonus(depth), rng);
+        for room in rooms.iter() {
+            let candidates: Vec<Vec<Vec<usize>>> = templates
+                .get(room.size as usize)
+                .into_iter()
+                .flat_map(|rooms| rooms.values())
+                .filter(|template| template.size == room.size)
+                .filter_map(|template| template.resolve(tiles))
+                .collect();
+
+            tracing::debug!("{:?}, {}, {}", room.position, room.size, candidates.len());
+            if candidates.is_empty() {
+                let size = glam::u16vec2(room.span() - 1, room.span() - 1);
+                self.make_room(room.position, size, wall, floor);
+            } else {
+                let data = &candidates[rng.gen_range(0..candidates.len())];
+                self.place(room.position, data);
+            }
+        }
+        self.connect(tiles, &rooms, rng);
+        self.repair(tiles);
+        rooms
+    }
+
+    // carve doors along a random spanning tree of the room adjacency graph
+    fn connect(&mut self, tiles: &IndexMap<String, Tile>, rooms: &[Room], rng: &mut GameRng) {
+        let door = tiles.get_index_of("door").unwrap();
+        let floor = tiles.get_index_of("tile").unwrap();
+
+        let mut edges = Vec::new();
+        for a in 0..rooms.len() {
+            for b in (a + 1)..rooms.len() {
+                if let Some(wall) = rooms[a].shared_wall(&rooms[b]) {
+                    edges.push((a, b, wall));
                 }
-                out.push(c);
             }
-            println!("{out}, {}", item.data);
         }
+        edges.shuffle(rng);
 
-        for room in rooms {
-            let mut position = glam::u16vec2(0, 0);
-            for i in 0..=5 {
-                let x = ((room.position >> (2 * i)) & 0b10) >> 1;
-                let y = (room.position >> (2 * i)) & 0b01;
-                position.x += (x * (32 >> i)) as u16;
-                position.y += (y * (32 >> i)) as u16;
+        let mut parent: Vec<usize> = (0..rooms.len()).collect();
+        fn find(parent: &mut [usize], i: usize) -> usize {
+            if parent[i] != i {
+                parent[i] = find(parent, parent[i]);
             }
-            println!("{position:?}, {}", room.data);
-            let size = glam::u16vec2(2 << room.data, 2 << room.data);
-            self.make_room(position, size, tile);
+            parent[i]
         }
-        for room in rooms2 {
-            let mut position = glam::u16vec2(64, 0);
-            for i in 0..=5 {
-                let x = ((room.position >> (2 * i)) & 0b10) >> 1;
-                let y = (room.position >> (2 * i)) & 0b01;
-                position.x += (x * (32 >> i)) as u16;
-                position.y += (y * (32 >> i)) as u16;
+
+        for (a, b, wall) in edges {
+            let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+            if root_a != root_b || rng.gen::<f32>() < LOOP_CHANCE {
+                parent[root_a] = root_b;
+                self.data[wall.y as usize][wall.x as usize] = if rng.gen::<f32>() < DOOR_CHANCE {
+                    door
+                } else {
+                    floor
+                };
+            }
+        }
+    }
+
+    fn passable(&self, tiles: &IndexMap<String, Tile>, position: glam::U16Vec2) -> bool {
+        let tile = self.data[position.y as usize][position.x as usize];
+        tiles[tile].r#move || Some(tile) == tiles.get_index_of("door")
+    }
+
+    pub fn reachable(
+        &self,
+        tiles: &IndexMap<String, Tile>,
+        start: glam::U16Vec2,
+    ) -> Vec<Vec<bool>> {
+        let mut reached = vec![vec![false; self.size.x as usize]; self.size.y as usize];
+        let mut queue = std::collections::VecDeque::from([start]);
+        reached[start.y as usize][start.x as usize] = true;
+        while let Some(position) = queue.pop_front() {
+            for next in self.neighbours(position) {
+                if !reached[next.y as usize][next.x as usize] && self.passable(tiles, next) {
+                    reached[next.y as usize][next.x as usize] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        reached
+    }
+
+    fn neighbours(&self, position: glam::U16Vec2) -> Vec<glam::U16Vec2> {
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .into_iter()
+            .map(|(x, y)| position.as_i16vec2() + glam::i16vec2(x, y))
+            .filter(|p| {
+                p.x >= 0 && p.y >= 0 && p.x < self.size.x as i16 && p.y < self.size.y as i16
+            })
+            .map(|p| p.as_u16vec2())
+            .collect()
+    }
+
+    // dig the shortest tunnel from the reachable area to each unreachable floor tile
+    fn repair(&mut self, tiles: &IndexMap<String, Tile>) {
+        let floor = tiles.get_index_of("tile").unwrap();
+        let Some(start) = self.floor_positions(tiles).first().copied() else {
+            return;
+        };
+        loop {
+            let reached = self.reachable(tiles, start);
+            let unreached: Vec<glam::U16Vec2> = self
+                .floor_positions(tiles)
+                .into_iter()
+                .filter(|p| !reached[p.y as usize][p.x as usize])
+                .collect();
+            if unreached.is_empty() {
+                break;
+            }
+            tracing::debug!("{} unreachable tiles", unreached.len());
+
+            // breadth first search through walls, starting from everything reachable
+            let mut from = vec![vec![None; self.size.x as usize]; self.size.y as usize];
+            let mut queue = std::collections::VecDeque::new();
+            for (i, row) in reached.iter().enumerate() {
+                for (j, reached) in row.iter().enumerate() {
+                    if *reached {
+                        let position = glam::u16vec2(j as u16, i as u16);
+                        from[i][j] = Some(position);
+                        queue.push_back(position);
+                    }
+                }
+            }
+            let mut target = None;
+            while let Some(position) = queue.pop_front() {
+                if self.passable(tiles, position)
+                    && !reached[position.y as usize][position.x as usize]
+                {
+                    target = Some(position);
+                    break;
+                }
+                for next in self.neighbours(position) {
+                    if from[next.y as usize][next.x as usize].is_none() {
+                        from[next.y as usize][next.x as usize] = Some(position);
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            let Some(mut position) = target else {
+                break;
+            };
+            while !reached[position.y as usize][position.x as usize] {
+                if !self.passable(tiles, position) {
+                    self.data[position.y as usize][position.x as usize] = floor;
+                }
+                position = from[position.y as usize][position.x as usize].unwrap();
             }
-            println!("{position:?}, {}", room.data);
-            let size = glam::u16vec2(2 << room.data, 2 << room.data);
-            self.make_room(position, size, tile);
         }
     }
 
-    fn make_room(&mut self, pos: glam::U16Vec2, size: glam::U16Vec2, tile: usize) {
+    pub fn floor_positions(&self, tiles: &IndexMap<String, Tile>) -> Vec<glam::U16Vec2> {
+        let void = tiles.get_index_of("void");
+        let mut positions = Vec::new();
+        for (i, row) in self.data.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if tiles[*tile].r#move && Some(*tile) != void {
+                    positions.push(glam::u16vec2(j as u16, i as u16));
+                }
+            }
+        }
+        positions
+    }
+
+    fn place(&mut self, pos: glam::U16Vec2, data: &[Vec<usize>]) {
+        for (i, row) in data.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                self.data[pos.y as usize + i][pos.x as usize + j] = *tile;
+            }
+        }
+    }
+
+    fn make_room(&mut self, pos: glam::U16Vec2, size: glam::U16Vec2, wall: usize, floor: usize) {
         // 2 x 2    0
         // 4 x 4    1
         // 8 x 8    2
@@ -87,24 +286,52 @@ impl Level {
         let (size_x, size_y) = (size.x as usize, size.y as usize);
         let (pos_x, pos_y) = (pos.x as usize, pos.y as usize);
 
+        for row in 1..size_y {
+            for col in 1..size_x {
+                self.data[pos_y + row][pos_x + col] = floor;
+            }
+        }
         for col in 0..=size_x {
-            self.data[pos_y][pos_x + col] = tile;
-            self.data[pos_y + size_y][pos_x + col] = tile;
+            self.data[pos_y][pos_x + col] = wall;
+            self.data[pos_y + size_y][pos_x + col] = wall;
         }
         for row in 0..=size_y {
-            self.data[pos_y + row][pos_x] = tile;
-            self.data[pos_y + row][pos_x + size_x] = tile;
+            self.data[pos_y + row][pos_x] = wall;
+            self.data[pos_y + row][pos_x + size_x] = wall;
         }
     }
 }
 
+fn layout(split_bonus: f32, rng: &mut GameRng) -> Vec<Room> {
+    let mut rooms = Vec::new();
+    for offset in [0, 64] {
+        let mut tree = Quadtree::new(0);
+        quadtree_gen(&mut tree, 5, split_bonus, rng);
+        for leaf in iter(&tree, 0, 0) {
+            let mut position = glam::u16vec2(offset, 0);
+            for i in 0..=5 {
+                let x = ((leaf.position >> (2 * i)) & 0b10) >> 1;
+                let y = (leaf.position >> (2 * i)) & 0b01;
+                position.x += (x * (32 >> i)) as u16;
+                position.y += (y * (32 >> i)) as u16;
+            }
+            // a leaf at depth n is 2 << n wide
+            rooms.push(Room {
+                position,
+                size: *leaf.data as u8 + 1,
+            });
+        }
+    }
+    rooms
+}
+
 const WEIGHTS: [f32; 6] = [0.0, 0.1, 0.2, 0.7, 0.9, 1.0];
 
-pub fn quadtree_gen(tree: &mut Quadtree<u16>, n: usize) {
+pub fn quadtree_gen(tree: &mut Quadtree<u16>, n: usize, split_bonus: f32, rng: &mut GameRng) {
     tree.subdivide();
     for i in 0..4 {
-        if (rand::random::<f32>() < WEIGHTS[n]) && n > 1 {
-            quadtree_gen(&mut tree[i], n - 1);
+        if (rng.gen::<f32>() < WEIGHTS[n] + split_bonus) && n > 1 {
+            quadtree_gen(&mut tree[i], n - 1, split_bonus, rng);
         } else {
             tree[i] = Quadtree::Leaf(n as u16 - 1);
         }
@@ -131,3 +358,62 @@ pub struct RoomTile {
     pub tiles: Vec<String>,
     pub data: Vec<Vec<usize>>,
 }
+
+impl RoomTile {
+    // room data as indices into the tile registry, None if the room is malformed
+    pub fn resolve(&self, tiles: &IndexMap<String, Tile>) -> Option<Vec<Vec<usize>>> {
+        let span = (1 << self.size) + 1;
+        let palette = self
+            .tiles
+            .iter()
+            .map(|name| tiles.get_index_of(name))
+            .collect::<Option<Vec<usize>>>()?;
+        if self.data.len() != span || self.data.iter().any(|row| row.len() != span) {
+            return None;
+        }
+        self.data
+            .iter()
+            .map(|row| row.iter().map(|i| palette.get(*i).copied()).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn generate(seed: u64, depth: usize) -> Level {
+        let tiles = crate::tile::import_tiles("res/tiles.toml").unwrap();
+        let mut level = Level::new(LEVEL_SIZE);
+        let mut rng = GameRng::seed_from_u64(crate::dungeon::floor_seed(seed, depth));
+        level.generate(&tiles, &import_rooms().unwrap(), depth, &mut rng);
+        level
+    }
+
+    #[test]
+    fn every_floor_tile_is_reachable() {
+        let tiles = crate::tile::import_tiles("res/tiles.toml").unwrap();
+        for seed in 0..8 {
+            for depth in 0..crate::dungeon::FLOORS {
+                let level = generate(seed, depth);
+                let floor = level.floor_positions(&tiles);
+                let reached = level.reachable(&tiles, floor[0]);
+                for position in floor {
+                    assert!(
+                        reached[position.y as usize][position.x as usize],
+                        "seed {seed} depth {depth}: {position} is cut off"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_level() {
+        assert_eq!(generate(11, 0).data, generate(11, 0).data);
+        assert_eq!(generate(11, 2).data, generate(11, 2).data);
+        assert_ne!(generate(11, 0).data, generate(12, 0).data);
+        assert_ne!(generate(11, 0).data, generate(11, 1).data);
+    }
+}
@@ -1,53 +1,131 @@
-use crossterm::terminal;
+use anyhow::Context;
 use indexmap::IndexMap;
-use std::io::{self, Write};
+mod dungeon;
 mod editor;
+mod entity;
+mod export;
+mod fov;
 mod game;
+mod headless;
+mod image;
 mod input;
+mod item;
+mod keymap;
 mod level;
+mod nutrition;
 mod renderer;
+mod replay;
+mod save;
+mod skills;
 mod term;
 mod tile;
+mod turn;
 mod ui;
 mod util;
+mod validate;
 use input::GetInput;
 use renderer::Renderer;
 use ui::Ui;
 
-use crate::tile::Tile;
-
 // default 80 x 24 window
-// hunger as action points lmao???
 
-fn main() -> anyhow::Result<()> {
+fn main() {
+    // a broken resource file or bad argument gets a readable message rather than a backtrace
+    if let Err(error) = start() {
+        eprintln!("error: {error}");
+        for cause in error.chain().skip(1) {
+            eprintln!("  {cause}");
+        }
+        std::process::exit(1);
+    }
+}
+
+fn start() -> anyhow::Result<()> {
     // logging
-    let log_file = std::fs::File::create("log.txt").unwrap();
+    let log_file = std::fs::File::create("log.txt").context("could not create log.txt")?;
     let subscriber = tracing_subscriber::fmt()
         .compact()
         .with_line_number(true)
         .with_writer(log_file)
         .finish();
-    tracing::subscriber::set_global_default(subscriber).unwrap();
+    tracing::subscriber::set_global_default(subscriber)?;
 
     let args: Vec<String> = std::env::args().collect();
 
-    let mut stdout = io::stdout();
-    let size = terminal::size()?;
-    // let mut level = level::Level::new(glam::u16vec2(129, 65));
-    // let mut level = level::Level::new(glam::u16vec2(3, 3));
-    // let mut level = level::Level::new(glam::u16vec2(5, 5));
-    let mut level = level::Level::new(glam::u16vec2(9, 9));
-    // let mut level = level::Level::new(glam::u16vec2(17, 17));
-    // let mut level = level::Level::new(glam::u16vec2(33, 33));
-
-    let tiles = util::import_toml::<tile::Tile>("res/tiles.toml");
-
-    println!("{:?}", level.size);
-    for i in 0..(level.size.y) {
-        for j in 0..(level.size.x) {
-            level.data[i as usize][j as usize] = tiles.get_index_of("tile").unwrap();
+    if args.len() > 1 && args[1] == "validate" {
+        let problems = validate::validate();
+        for problem in problems.iter() {
+            eprintln!("{problem}");
+        }
+        if !problems.is_empty() {
+            eprintln!("{} problems found", problems.len());
+            std::process::exit(1);
         }
+        println!("resources are valid");
+        return Ok(());
+    }
+
+    // `import picture.png name` turns an image into a room through res/legend.toml
+    if args.len() > 1 && args[1] == "import" {
+        let (Some(path), Some(name)) = (args.get(2), args.get(3)) else {
+            anyhow::bail!("usage: import <image> <room name>");
+        };
+        let tiles = util::import_toml::<tile::Tile>("res/tiles.toml")?;
+        let image = image::load(path)?;
+        let size = (0..level::ROOM_SIZES)
+            .find(|x| image.size == glam::U16Vec2::splat((1 << x) + 1))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "image is {}x{}, rooms are 2x2, 3x3, 5x5, 9x9 or 17x17",
+                    image.size.x,
+                    image.size.y
+                )
+            })?;
+        let level = image::to_level(&image, &image::import_legend(image::LEGEND_PATH)?, &tiles)?;
+        editor::export(name.clone(), &tiles, &level)?;
+        println!("saved {name} as a size {size} room");
+        return Ok(());
     }
+
+    let editor = args.len() > 1 && args[1] == "editor";
+    // `export map.html --seed 1 --depth 2` writes a whole generated floor as ansi, html or text
+    let export_path = match args.get(1).filter(|x| *x == "export") {
+        Some(_) => Some(
+            args.get(2)
+                .filter(|x| !x.starts_with("--"))
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("usage: export <file.ans|html|txt>"))?,
+        ),
+        None => None,
+    };
+    let headless = export_path.is_some() || args.iter().any(|x| x == "--headless");
+    let seed = match args.iter().position(|x| x == "--seed") {
+        Some(i) => Some(
+            args.get(i + 1)
+                .and_then(|x| x.parse::<u64>().ok())
+                .ok_or_else(|| anyhow::anyhow!("--seed expects a number"))?,
+        ),
+        None => None,
+    };
+    let path_arg = |flag: &str| -> anyhow::Result<Option<String>> {
+        match args.iter().position(|x| x == flag) {
+            Some(i) => {
+                Ok(Some(args.get(i + 1).cloned().ok_or_else(|| {
+                    anyhow::anyhow!("{flag} expects a file path")
+                })?))
+            }
+            None => Ok(None),
+        }
+    };
+    let replay = path_arg("--replay")?;
+    let record = path_arg("--record")?;
+
+    let tiles = tile::import_tiles("res/tiles.toml")?;
+    let entity_types = util::import_toml::<entity::Entity>("res/entity.toml")?;
+    let item_types = util::import_toml::<item::Item>("res/items.toml")?;
+    let action_costs = util::import_toml::<turn::ActionCost>("res/actions.toml")?;
+
+    let level = level::Level::new(level::LEVEL_SIZE);
     // 13 x 7   5
     // 2 x 2    4
     // 4 x 4    3
@@ -56,50 +134,113 @@ fn main() -> anyhow::Result<()> {
     // 32 x 32  0
 
     let mut renderer = term::Terminal::new(tiles.clone());
-    let inputs = Box::new(term::Terminal::new(tiles.clone()));
-    let mut state = crate::game::GameState::init(&renderer, inputs, level, tiles);
-
-    let display_tiles: Vec<Vec<Tile>> = state
-        .tiles
-        .iter()
-        .map(|(key, tile)| {
-            let mut vec = vec![*tile, Tile::new(' ', 0, 0, false)];
-            vec.extend(Tile::from_string(key, Some(15), Some(0)));
-            vec
-        })
-        .collect();
-
-    state.ui.push(ui::Menu::new(
-        "Tiles",
-        glam::u16vec2(1, 1),
-        glam::u16vec2(30, 15),
-        display_tiles,
-    ));
-
-    state.ui[0].next();
-    state.ui[0].next();
+    let mut headless_renderer = headless::Headless::new(glam::u16vec2(80, 24));
+    let script = replay.as_deref().map(replay::Script::load).transpose()?;
+    // an explicit --seed wins over the one a recording was made with
+    let seed = seed
+        .or_else(|| script.as_ref().and_then(|script| script.seed))
+        .unwrap_or_else(rand::random);
+    let mut inputs: Box<dyn GetInput> = match script {
+        Some(script) => Box::new(script),
+        // an export never reads input, and nothing else may touch the terminal
+        None if export_path.is_some() => Box::new(replay::Script::new(Vec::new())),
+        None if headless => anyhow::bail!("--headless needs --replay <file> to read input from"),
+        None => {
+            let mut terminal = term::Terminal::new(tiles.clone());
+            terminal.keymap = keymap::Keymap::load(keymap::KEYMAP_PATH)?;
+            Box::new(terminal)
+        }
+    };
+    if let Some(path) = &record {
+        inputs = Box::new(replay::Recorder::new(inputs, path, seed)?);
+    }
+    let mut state = crate::game::GameState::init(
+        if headless {
+            &headless_renderer
+        } else {
+            &renderer
+        },
+        inputs,
+        level,
+        tiles,
+        entity_types,
+        item_types,
+        action_costs,
+    )?;
+    state.reseed(seed);
+
+    if let Some(path) = export_path {
+        let format = export::Format::from_path(&path)
+            .ok_or_else(|| anyhow::anyhow!("{path} should end in .ans, .html or .txt"))?;
+        let depth = match args.iter().position(|x| x == "--depth") {
+            Some(i) => args
+                .get(i + 1)
+                .and_then(|x| x.parse::<usize>().ok())
+                .filter(|x| (1..=dungeon::FLOORS).contains(x))
+                .ok_or_else(|| anyhow::anyhow!("--depth expects 1 to {}", dungeon::FLOORS))?,
+            None => 1,
+        };
+        state.depth = depth - 1;
+        state.room_templates = level::import_rooms()?;
+        state.generate_floor();
+        export::write(
+            &path,
+            &export::level_tiles(&state.level, &state.tiles),
+            format,
+        )?;
+        return Ok(());
+    }
 
+    if editor {
+        // `editor 2` starts a blank room of size 2, `editor name` opens a saved room
+        let arg = args.get(2).filter(|x| !x.starts_with("--"));
+        let size = match arg.map(|x| x.parse::<u8>()) {
+            Some(Ok(size)) if size < level::ROOM_SIZES => size,
+            Some(Ok(_)) => anyhow::bail!("room size must be below {}", level::ROOM_SIZES),
+            _ => editor::DEFAULT_ROOM_SIZE,
+        };
+        state.editor = Some(editor::Editor::new(&state.tiles));
+        state.set_room_size(size);
+        if let Some(name) = arg.filter(|x| x.parse::<u8>().is_err()) {
+            let size = editor::find_room(name)?
+                .ok_or_else(|| anyhow::anyhow!("no saved room called {name}"))?;
+            state.open_room(size, name)?;
+        }
+    } else {
+        state.room_templates = level::import_rooms()?;
+        state.generate_floor();
+        // recordings always start from a fresh game, so they play back the same way
+        if replay.is_none() && record.is_none() && std::path::Path::new(save::SAVE_PATH).exists() {
+            state.resume_menu = Some(state.make_resume_menu());
+        }
+    }
+
+    if headless {
+        run(&mut headless_renderer, &mut state)?;
+        print!("{}", headless_renderer.text());
+    } else {
+        run(&mut renderer, &mut state)?;
+    }
+
+    Ok(())
+}
+
+fn run(renderer: &mut dyn Renderer, state: &mut game::GameState) -> anyhow::Result<()> {
     renderer.init()?;
 
-    loop {
+    // the terminal is restored even when rendering fails
+    let result = loop {
         state.update();
         if state.quit {
-            break;
+            break Ok(());
         }
-        // state.resize(renderer.resize());
-        renderer.render(&state)?;
-    }
-
-    renderer.quit()?;
-
-    if args.len() > 1 {
-        println!("{args:?}");
-        if args[1] == "editor" {
-            editor::export(state.name, &state.tiles, &state.level)?;
+        if let Err(error) = renderer.render(state) {
+            break Err(error);
         }
-    }
+    };
 
-    Ok(())
+    renderer.quit()?;
+    result
 }
 
 // render
@@ -0,0 +1,161 @@
+use std::ops::{Index, IndexMut};
+
+// full reserves last this many turns without eating
+const BURN_TURNS: f32 = 1000.0;
+const MAX_CALORIES: f32 = 2000.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Nutrient {
+    Protein,
+    Fat,
+    Carbs,
+    Vege,
+}
+
+impl Nutrient {
+    pub const ALL: [Nutrient; 4] = [
+        Nutrient::Protein,
+        Nutrient::Fat,
+        Nutrient::Carbs,
+        Nutrient::Vege,
+    ];
+
+    // fraction of a full reserve below which the deficiency kicks in
+    pub fn threshold(&self) -> f32 {
+        match self {
+            Nutrient::Protein => 0.2,
+            Nutrient::Fat => 0.15,
+            Nutrient::Carbs => 0.2,
+            Nutrient::Vege => 0.25,
+        }
+    }
+
+    pub fn deficiency(&self) -> Deficiency {
+        match self {
+            Nutrient::Protein => Deficiency::Weakness,
+            Nutrient::Fat => Deficiency::Nausea,
+            Nutrient::Carbs => Deficiency::Fatigue,
+            Nutrient::Vege => Deficiency::Blindness,
+        }
+    }
+
+    // grams for a full reserve
+    pub fn max(&self) -> f32 {
+        match self {
+            Nutrient::Protein => 75.0,
+            Nutrient::Fat => 70.0,
+            Nutrient::Carbs => 275.0,
+            Nutrient::Vege => 400.0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Deficiency {
+    Weakness,  // protein skills halved
+    Nausea,    // blurred rendering
+    Fatigue,   // actions take twice as long
+    Blindness, // reduced sight radius
+}
+
+impl Deficiency {
+    pub fn onset_message(&self) -> &'static str {
+        match self {
+            Deficiency::Weakness => "Your muscles feel weak.",
+            Deficiency::Nausea => "You feel nauseous, the world swims before your eyes.",
+            Deficiency::Fatigue => "You feel fatigued, every step is an effort.",
+            Deficiency::Blindness => "Your vision is dimming.",
+        }
+    }
+
+    pub fn recovery_message(&self) -> &'static str {
+        match self {
+            Deficiency::Weakness => "Your strength returns.",
+            Deficiency::Nausea => "Your stomach settles.",
+            Deficiency::Fatigue => "You feel rested.",
+            Deficiency::Blindness => "Your vision clears.",
+        }
+    }
+}
+
+// used both for the player's reserves and for what a food item provides
+#[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Nutrition {
+    pub calories: f32,
+    pub protein: f32,
+    pub fat: f32,
+    pub carbs: f32,
+    pub vege: f32,
+}
+
+impl Nutrition {
+    pub fn full() -> Self {
+        let mut nutrition = Self {
+            calories: MAX_CALORIES,
+            ..Default::default()
+        };
+        for nutrient in Nutrient::ALL {
+            nutrition[nutrient] = nutrient.max();
+        }
+        nutrition
+    }
+
+    pub fn fraction(&self, nutrient: Nutrient) -> f32 {
+        (self[nutrient] / nutrient.max()).clamp(0.0, 1.0)
+    }
+
+    pub fn calorie_fraction(&self) -> f32 {
+        (self.calories / MAX_CALORIES).clamp(0.0, 1.0)
+    }
+
+    pub fn eat(&mut self, food: &Nutrition) {
+        self.calories = (self.calories + food.calories).min(MAX_CALORIES);
+        for nutrient in Nutrient::ALL {
+            self[nutrient] = (self[nutrient] + food[nutrient]).min(nutrient.max());
+        }
+    }
+
+    pub fn burn(&mut self, turns: f32) {
+        self.calories = (self.calories - MAX_CALORIES / BURN_TURNS * turns).max(0.0);
+        for nutrient in Nutrient::ALL {
+            self[nutrient] = (self[nutrient] - nutrient.max() / BURN_TURNS * turns).max(0.0);
+        }
+    }
+
+    pub fn deficiencies(&self) -> Vec<Deficiency> {
+        Nutrient::ALL
+            .into_iter()
+            .filter(|nutrient| self.fraction(*nutrient) < nutrient.threshold())
+            .map(|nutrient| nutrient.deficiency())
+            .collect()
+    }
+
+    pub fn starving(&self) -> bool {
+        self.calories <= 0.0
+    }
+}
+
+impl Index<Nutrient> for Nutrition {
+    type Output = f32;
+
+    fn index(&self, nutrient: Nutrient) -> &Self::Output {
+        match nutrient {
+            Nutrient::Protein => &self.protein,
+            Nutrient::Fat => &self.fat,
+            Nutrient::Carbs => &self.carbs,
+            Nutrient::Vege => &self.vege,
+        }
+    }
+}
+
+impl IndexMut<Nutrient> for Nutrition {
+    fn index_mut(&mut self, nutrient: Nutrient) -> &mut Self::Output {
+        match nutrient {
+            Nutrient::Protein => &mut self.protein,
+            Nutrient::Fat => &mut self.fat,
+            Nutrient::Carbs => &mut self.carbs,
+            Nutrient::Vege => &mut self.vege,
+        }
+    }
+}
@@ -22,6 +22,7 @@ const TILE_VOID: Tile = Tile {
     fore: 0,
     back: 0,
     r#move: true,
+    opaque: false,
 };
 
 impl Display {
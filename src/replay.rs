@@ -0,0 +1,113 @@
+use crate::input::{GetInput, Input, TextInput};
+use anyhow::Context;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+
+// one line per event in a recording
+#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Event {
+    // written first, so a recording plays back on the dungeon it was made in
+    Seed(u64),
+    Input(Input),
+    Text(TextInput),
+}
+
+// plays back a fixed list of events, quitting once they run out
+pub struct Script {
+    events: RefCell<VecDeque<Event>>,
+    pub seed: Option<u64>,
+}
+
+impl Script {
+    pub fn new(mut events: Vec<Event>) -> Self {
+        let seed = match events.first() {
+            Some(&Event::Seed(seed)) => Some(seed),
+            _ => None,
+        };
+        if seed.is_some() {
+            events.remove(0);
+        }
+        Self {
+            events: RefCell::new(events.into()),
+            seed,
+        }
+    }
+
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path).with_context(|| format!("could not open {path}"))?;
+        let mut events = Vec::new();
+        for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("could not read {path}"))?;
+            if !line.trim().is_empty() {
+                events.push(
+                    serde_json::from_str(&line)
+                        .with_context(|| format!("bad event on line {} of {path}", i + 1))?,
+                );
+            }
+        }
+        Ok(Self::new(events))
+    }
+}
+
+impl GetInput for Script {
+    fn get_input(&self) -> Input {
+        match self.events.borrow_mut().pop_front() {
+            Some(Event::Input(input)) => input,
+            Some(Event::Text(_) | Event::Seed(_)) => Input::None,
+            None => Input::Quit,
+        }
+    }
+
+    fn get_text_input(&self) -> TextInput {
+        match self.events.borrow_mut().pop_front() {
+            Some(Event::Text(input)) => input,
+            Some(Event::Input(_) | Event::Seed(_)) => TextInput::None,
+            None => TextInput::Exit,
+        }
+    }
+}
+
+// passes input through from another source, writing everything but idle polls to a file
+pub struct Recorder {
+    inner: Box<dyn GetInput>,
+    file: RefCell<std::fs::File>,
+}
+
+impl Recorder {
+    pub fn new(inner: Box<dyn GetInput>, path: &str, seed: u64) -> anyhow::Result<Self> {
+        let recorder = Self {
+            inner,
+            file: RefCell::new(
+                std::fs::File::create(path).with_context(|| format!("could not create {path}"))?,
+            ),
+        };
+        recorder.record(Event::Seed(seed));
+        Ok(recorder)
+    }
+
+    fn record(&self, event: Event) {
+        let line = serde_json::to_string(&event).unwrap();
+        if let Err(error) = writeln!(self.file.borrow_mut(), "{line}") {
+            tracing::error!("could not record input: {error}");
+        }
+    }
+}
+
+impl GetInput for Recorder {
+    fn get_input(&self) -> Input {
+        let input = self.inner.get_input();
+        if !matches!(input, Input::None) {
+            self.record(Event::Input(input));
+        }
+        input
+    }
+
+    fn get_text_input(&self) -> TextInput {
+        let input = self.inner.get_text_input();
+        if !matches!(input, TextInput::None) {
+            self.record(Event::Text(input));
+        }
+        input
+    }
+}
@@ -0,0 +1,337 @@
+use crate::dungeon::{self, Floor};
+use crate::entity::Entity;
+use crate::game::GameState;
+use crate::item::Item;
+use crate::level::Level;
+use crate::nutrition::Nutrition;
+use crate::skills::Skills;
+use crate::tile::Tile;
+use crate::util::GameRng;
+use anyhow::Context;
+use indexmap::IndexMap;
+
+pub const SAVE_PATH: &str = "save.json";
+
+// tiles and items are stored by name so a save survives reordering the toml files
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedLevel {
+    size: glam::U16Vec2,
+    tiles: Vec<String>,
+    data: Vec<Vec<usize>>, // indices into tiles above
+    explored: Vec<Vec<bool>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedFloor {
+    level: SavedLevel,
+    entities: Vec<Entity>,
+    floor_items: Vec<(glam::U16Vec2, String)>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Save {
+    seed: u64,
+    rng: GameRng,
+    depth: usize,
+    nutrition: Nutrition,
+    skills: Skills,
+    messages: Vec<String>,
+    entities: Vec<Entity>, // the player first, as in GameState
+    inventory: Vec<String>,
+    held: Option<usize>,
+    current: SavedFloor,
+    floors: Vec<Option<SavedFloor>>,
+}
+
+impl SavedLevel {
+    fn new(level: &Level, tiles: &IndexMap<String, Tile>) -> Self {
+        let mut used: Vec<usize> = level.data.iter().flatten().copied().collect();
+        used.sort();
+        used.dedup();
+        let data = level
+            .data
+            .iter()
+            .map(|row| row.iter().map(|x| used.binary_search(x).unwrap()).collect())
+            .collect();
+        Self {
+            size: level.size,
+            tiles: used
+                .into_iter()
+                .map(|x| tiles.get_index(x).unwrap().0.clone())
+                .collect(),
+            data,
+            explored: level.explored.clone(),
+        }
+    }
+
+    fn level(self, tiles: &IndexMap<String, Tile>) -> anyhow::Result<Level> {
+        let legend = self
+            .tiles
+            .iter()
+            .map(|name| {
+                tiles
+                    .get_index_of(name)
+                    .with_context(|| format!("unknown tile `{name}`"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if !fits(&self.data, self.size) || !fits(&self.explored, self.size) {
+            anyhow::bail!(
+                "level does not match its size {}x{}",
+                self.size.x,
+                self.size.y
+            );
+        }
+        let mut data = Vec::new();
+        for row in self.data {
+            let row = row
+                .into_iter()
+                .map(|x| legend.get(x).copied().context("tile index out of range"))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            data.push(row);
+        }
+        Ok(Level {
+            size: self.size,
+            data,
+            explored: self.explored,
+        })
+    }
+}
+
+// rows are indexed by y, as in Level::new
+fn fits<T>(rows: &[Vec<T>], size: glam::U16Vec2) -> bool {
+    rows.len() == size.y as usize && rows.iter().all(|row| row.len() == size.x as usize)
+}
+
+impl SavedFloor {
+    fn new(
+        level: &Level,
+        entities: &[Entity],
+        floor_items: &[(glam::U16Vec2, usize)],
+        state: &GameState,
+    ) -> Self {
+        Self {
+            level: SavedLevel::new(level, &state.tiles),
+            entities: entities.to_vec(),
+            floor_items: floor_items
+                .iter()
+                .map(|(position, item)| (*position, item_name(&state.item_types, *item)))
+                .collect(),
+        }
+    }
+
+    fn floor(self, state: &GameState) -> anyhow::Result<Floor> {
+        let floor_items = self
+            .floor_items
+            .into_iter()
+            .map(|(position, name)| Ok((position, item_index(&state.item_types, &name)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let level = self.level.level(&state.tiles)?;
+        check_positions(&level, &self.entities, &floor_items)?;
+        Ok(Floor {
+            level,
+            entities: self.entities,
+            floor_items,
+        })
+    }
+}
+
+// a position outside its level would panic the first time it is drawn or looked at
+fn check_positions(
+    level: &Level,
+    entities: &[Entity],
+    floor_items: &[(glam::U16Vec2, usize)],
+) -> anyhow::Result<()> {
+    let inside = |position: glam::U16Vec2| position.cmplt(level.size).all();
+    if let Some(entity) = entities.iter().find(|x| !inside(x.position)) {
+        anyhow::bail!(
+            "{} at {} is outside the level",
+            entity.kind,
+            entity.position
+        );
+    }
+    if let Some((position, _)) = floor_items.iter().find(|(x, _)| !inside(*x)) {
+        anyhow::bail!("item at {position} is outside the level");
+    }
+    Ok(())
+}
+
+fn item_name(items: &IndexMap<String, Item>, index: usize) -> String {
+    items.get_index(index).unwrap().0.clone()
+}
+
+fn item_index(items: &IndexMap<String, Item>, name: &str) -> anyhow::Result<usize> {
+    items
+        .get_index_of(name)
+        .with_context(|| format!("unknown item `{name}`"))
+}
+
+impl GameState {
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        let floors = self
+            .floors
+            .iter()
+            .map(|floor| {
+                floor.as_ref().map(|floor| {
+                    SavedFloor::new(&floor.level, &floor.entities, &floor.floor_items, self)
+                })
+            })
+            .collect();
+        let save = Save {
+            seed: self.seed,
+            rng: self.rng.clone(),
+            depth: self.depth,
+            nutrition: self.nutrition,
+            skills: self.skills.clone(),
+            messages: self.messages.clone(),
+            entities: self.entities.clone(),
+            inventory: self
+                .inventory
+                .iter()
+                .map(|x| item_name(&self.item_types, *x))
+                .collect(),
+            held: self.held,
+            current: SavedFloor::new(&self.level, &[], &self.floor_items, self),
+            floors,
+        };
+        let json = serde_json::to_string(&save)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    // state is only touched once the whole save has been read successfully
+    pub fn load(&mut self, path: &str) -> anyhow::Result<()> {
+        let json = std::fs::read_to_string(path)?;
+        let save: Save = serde_json::from_str(&json)?;
+        if save.entities.is_empty() {
+            anyhow::bail!("save has no player");
+        }
+        if save.depth >= dungeon::FLOORS {
+            anyhow::bail!("save is on floor {} of {}", save.depth + 1, dungeon::FLOORS);
+        }
+        if save.floors.len() != dungeon::FLOORS {
+            anyhow::bail!(
+                "save has {} floors, expected {}",
+                save.floors.len(),
+                dungeon::FLOORS
+            );
+        }
+
+        let current = save.current.floor(self)?;
+        check_positions(&current.level, &save.entities, &[])?;
+        let mut floors = Vec::new();
+        for floor in save.floors {
+            floors.push(floor.map(|floor| floor.floor(self)).transpose()?);
+        }
+        let inventory = save
+            .inventory
+            .iter()
+            .map(|name| item_index(&self.item_types, name))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        self.seed = save.seed;
+        self.rng = save.rng;
+        self.depth = save.depth;
+        self.nutrition = save.nutrition;
+        self.skills = save.skills;
+        self.deficiencies = self.nutrition.deficiencies();
+        self.messages = save.messages;
+        self.entities = save.entities;
+        self.inventory = inventory;
+        self.held = save.held.filter(|x| *x < self.inventory.len());
+        self.level = current.level;
+        self.floor_items = current.floor_items;
+        self.floors = floors;
+        self.inventory_menu = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::Headless;
+    use crate::replay::Script;
+    use crate::{level, tile, util};
+
+    fn state(seed: u64) -> GameState {
+        let mut state = GameState::init(
+            &Headless::new(glam::u16vec2(80, 24)),
+            Box::new(Script::new(Vec::new())),
+            Level::new(level::LEVEL_SIZE),
+            tile::import_tiles("res/tiles.toml").unwrap(),
+            util::import_toml("res/entity.toml").unwrap(),
+            util::import_toml("res/items.toml").unwrap(),
+            util::import_toml("res/actions.toml").unwrap(),
+        )
+        .unwrap();
+        state.reseed(seed);
+        state.room_templates = level::import_rooms().unwrap();
+        state.generate_floor();
+        state
+    }
+
+    fn path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("grubgeon_{name}.json"))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn positions(entities: &[Entity]) -> Vec<(String, glam::U16Vec2)> {
+        entities
+            .iter()
+            .map(|x| (x.kind.clone(), x.position))
+            .collect()
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut saved = state(5);
+        saved.message("hello");
+        saved.inventory.push(1);
+        saved.held = Some(0);
+        let other = state(6);
+        saved.floors[1] = Some(Floor {
+            level: other.level,
+            entities: other.entities[1..].to_vec(),
+            floor_items: other.floor_items,
+        });
+        let path = path("round_trip");
+        saved.save(&path).unwrap();
+
+        let mut loaded = state(7);
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.seed, 5);
+        assert_eq!(loaded.messages, saved.messages);
+        assert_eq!(loaded.inventory, saved.inventory);
+        assert_eq!(loaded.held, saved.held);
+        assert_eq!(loaded.level.data, saved.level.data);
+        assert_eq!(loaded.level.explored, saved.level.explored);
+        assert_eq!(loaded.floor_items, saved.floor_items);
+        assert_eq!(positions(&loaded.entities), positions(&saved.entities));
+        let (loaded, saved) = (
+            loaded.floors[1].as_ref().unwrap(),
+            saved.floors[1].as_ref().unwrap(),
+        );
+        assert_eq!(loaded.level.data, saved.level.data);
+        assert_eq!(loaded.floor_items, saved.floor_items);
+        assert_eq!(positions(&loaded.entities), positions(&saved.entities));
+    }
+
+    #[test]
+    fn rejects_position_outside_level() {
+        let path = path("outside");
+        state(5).save(&path).unwrap();
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json["entities"][0]["position"] = serde_json::json!([999, 999]);
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let mut loaded = state(7);
+        let error = loaded.load(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error, "player at [999, 999] is outside the level");
+        assert_eq!(loaded.seed, 7);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::nutrition::{Nutrient, Nutrition};
+
+//| Nutrition |           |            |           |           |
+//|-----------|-----------|------------|-----------|-----------|
+//| Protein   | Slaughter | Hunting    | Recovery  | Strength  |
+//| Fats      | Cooking   | Stomaching | Endurance | Fire      |
+//| Carbs     | Athletics | Acrobatics | Reaction  | Coord     |
+//| Vitamins  | Foraging  | Tracking   | Analysis  | Magick    |
+
+const BASE_LEVEL: u16 = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Skill {
+    Slaughter,
+    Hunting,
+    Recovery,
+    Strength,
+    Cooking,
+    Stomaching,
+    Endurance,
+    Fire,
+    Athletics,
+    Acrobatics,
+    Reaction,
+    Coordination,
+    Foraging,
+    Tracking,
+    Analysis,
+    Magick,
+}
+
+impl Skill {
+    pub fn nutrient(&self) -> Nutrient {
+        Nutrient::ALL[*self as usize / 4]
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Skills {
+    base: [u16; 16],
+}
+
+impl Default for Skills {
+    fn default() -> Self {
+        Self {
+            base: [BASE_LEVEL; 16],
+        }
+    }
+}
+
+impl Skills {
+    // the trained level scaled by how full the skill's nutrient reserve is,
+    // half the base level when empty up to one and a half times when full
+    pub fn level(&self, skill: Skill, nutrition: &Nutrition) -> u16 {
+        let fraction = nutrition.fraction(skill.nutrient());
+        (self.base[skill as usize] as f32 * (0.5 + fraction)).round() as u16
+    }
+}
@@ -2,24 +2,47 @@ use std::io;
 
 use crate::game::GameState;
 use crate::input::{GetInput, Input, TextInput};
+use crate::keymap::Keymap;
+use crate::nutrition::Nutrient;
 use crate::renderer::Renderer;
 use crate::tile::Tile;
-use crossterm::event::{poll, read, Event, KeyCode};
+use crossterm::event::{
+    poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, MouseButton,
+    MouseEventKind,
+};
 use crossterm::{cursor, style, terminal, ExecutableCommand, QueueableCommand};
 use indexmap::IndexMap;
+use std::cell::Cell;
 use std::io::Write;
 use std::time::Duration;
 
 pub struct Terminal {
     stdout: io::Stdout,
     pub tiles: IndexMap<String, Tile>,
+    // what is already on screen, so only changes are sent
+    previous: Vec<Vec<Tile>>,
+    status: String,
+    fore: Option<u8>,
+    back: Option<u8>,
+    // a resize or mouse event seen while reading keys, handed out by the next get_input
+    pending: Cell<Option<Input>>,
+    pub keymap: Keymap,
 }
 
 impl Terminal {
     pub fn new(tiles: IndexMap<String, Tile>) -> Self {
         let stdout = io::stdout();
 
-        Self { stdout, tiles }
+        Self {
+            stdout,
+            tiles,
+            previous: Vec::new(),
+            status: String::new(),
+            fore: None,
+            back: None,
+            pending: Cell::new(None),
+            keymap: Keymap::default(),
+        }
     }
 }
 
@@ -34,10 +57,14 @@ impl Renderer for Terminal {
             .execute(terminal::Clear(terminal::ClearType::All))?;
         terminal::enable_raw_mode().unwrap();
         self.stdout.execute(cursor::Hide)?;
+        self.stdout.execute(EnableMouseCapture)?;
+        self.previous.clear();
+        self.status.clear();
         Ok(())
     }
 
     fn quit(&mut self) -> anyhow::Result<()> {
+        self.stdout.execute(DisableMouseCapture)?;
         terminal::disable_raw_mode().unwrap();
         self.stdout
             .execute(terminal::Clear(terminal::ClearType::All))?;
@@ -51,70 +78,126 @@ impl Renderer for Terminal {
     }
 
     fn render(&mut self, state: &GameState) -> anyhow::Result<()> {
-        let diff = (state.display.size / 2).as_i16vec2();
-        let ipos = state.position.as_i16vec2();
-        let start = glam::i16vec2(ipos.x - diff.x, ipos.y - diff.y);
-        let end = glam::i16vec2(ipos.x + diff.x, ipos.y + diff.y);
-
-        tracing::info!("{start:?}, {end:?}");
-
-        self.stdout.queue(cursor::MoveTo(0, 0))?;
-
-        self.stdout
-            .queue(style::SetForegroundColor(style::Color::DarkRed))?;
-        self.stdout.queue(style::Print(&format!(
-            "HLTH: {} ",
-            text_bar(state.health as u8, 160, false)
-        )))?;
-        self.stdout
-            .queue(style::SetForegroundColor(style::Color::Red))?;
+        tracing::info!("{:?} {:?}", state.player().position, state.camera());
 
-        self.stdout.queue(style::Print(&format!(
-            "HUNG: {} ",
-            text_bar(state.hunger as u8 / 2, 184, false)
-        )))?;
+        // redraw everything if the display changed size, otherwise only changed cells
+        // the screen is cleared first so nothing is left past the new edges
+        let full = self.previous.len() != state.display.data.len()
+            || self.previous.first().map(Vec::len) != state.display.data.first().map(Vec::len);
+        if full {
+            self.stdout
+                .queue(terminal::Clear(terminal::ClearType::All))?;
+            self.status.clear();
+        }
 
-        println!("\r\n{:?} {:?} {:?}", state.position, start, end);
+        // the status lines only change with these, so skip them when they are the same
+        let status = format!(
+            "{} {} {:?} {:?} {} {}",
+            state.player().health,
+            (state.nutrition.calorie_fraction() * 96.0) as u8,
+            Nutrient::ALL.map(|x| (state.nutrition.fraction(x) * 40.0) as u8),
+            state.messages.last(),
+            state.depth,
+            state.seed
+        );
+        if status != self.status {
+            self.stdout.queue(cursor::MoveTo(0, 0))?;
 
-        // self.queue(style::SetForegroundColor(style::Color::DarkRed))?;
-        // self.queue(style::Print(&format!("NUTR: {}", text_bar(40, 40, false))))?;
+            self.stdout
+                .queue(style::SetForegroundColor(style::Color::DarkRed))?;
+            // the bar is always 160 wide, whatever the player type's maximum health is
+            let max_health = state.entity_types["player"].health.max(1) as f32;
+            let health = (state.player().health as f32 / max_health).min(1.0);
+            self.stdout.queue(style::Print(&format!(
+                "HLTH: {} ",
+                text_bar((health * 160.0) as u8, 160, false)
+            )))?;
+            self.stdout
+                .queue(style::SetForegroundColor(style::Color::Red))?;
 
-        // self.queue(style::SetForegroundColor(style::Color::DarkYellow))?;
-        // self.queue(style::Print(&format!("{}", text_bar(40, 40, false))))?;
+            self.stdout.queue(style::Print(&format!(
+                "HUNG: {} ",
+                text_bar((state.nutrition.calorie_fraction() * 96.0) as u8, 96, false)
+            )))?;
 
-        // self.queue(style::SetForegroundColor(style::Color::Cyan))?;
-        // self.queue(style::Print(&format!("{}", text_bar(40, 40, false))))?;
+            self.stdout.queue(cursor::MoveTo(52, 0))?;
+            self.stdout
+                .queue(style::SetForegroundColor(style::Color::DarkRed))?;
+            self.stdout.queue(style::Print("NUTR: "))?;
+            for nutrient in Nutrient::ALL {
+                let color = match nutrient {
+                    Nutrient::Protein => style::Color::DarkRed,
+                    Nutrient::Fat => style::Color::DarkYellow,
+                    Nutrient::Carbs => style::Color::Cyan,
+                    Nutrient::Vege => style::Color::DarkGreen,
+                };
+                self.stdout.queue(style::SetForegroundColor(color))?;
+                self.stdout.queue(style::Print(text_bar(
+                    (state.nutrition.fraction(nutrient) * 40.0) as u8,
+                    40,
+                    false,
+                )))?;
+            }
 
-        // self.queue(style::SetForegroundColor(style::Color::DarkGreen))?;
-        // self.queue(style::Print(&format!("{}", text_bar(40, 40, false))))?;
+            self.stdout
+                .queue(style::SetForegroundColor(style::Color::Reset))?;
 
-        self.stdout
-            .queue(style::SetForegroundColor(style::Color::Reset))?;
+            self.stdout.queue(cursor::MoveTo(0, 1))?;
+            self.stdout
+                .queue(terminal::Clear(terminal::ClearType::CurrentLine))?;
+            if let Some(message) = state.messages.last() {
+                self.stdout.queue(style::Print(message))?;
+            }
+            let seed = format!("DEPTH: {} SEED: {}", state.depth + 1, state.seed);
+            self.stdout.queue(cursor::MoveTo(
+                state.display.size.x.saturating_sub(seed.len() as u16),
+                1,
+            ))?;
+            self.stdout.queue(style::Print(seed))?;
+            self.status = status;
+            (self.fore, self.back) = (None, None);
+        }
 
-        self.stdout.queue(cursor::MoveTo(0, 2))?;
+        let mut cursor = None;
         for i in 0..state.display.size.y {
             for j in 0..state.display.size.x {
                 let tile = state.display.data[i as usize][j as usize];
+                if !full && self.previous[i as usize][j as usize] == tile {
+                    continue;
+                }
+                if cursor != Some((j, i + 2)) {
+                    self.stdout.queue(cursor::MoveTo(j, i + 2))?;
+                }
                 self.tile(&tile)?;
+                cursor = Some((j + 1, i + 2));
             }
-            self.stdout.queue(style::Print("\n\r"))?;
         }
-        self.stdout
-            .queue(style::SetBackgroundColor(style::Color::Reset))?;
+        self.previous.clone_from(&state.display.data);
+        if cursor.is_some() {
+            self.stdout
+                .queue(style::SetBackgroundColor(style::Color::Reset))?;
+            self.back = None;
+        }
 
         self.stdout.flush()?;
         Ok(())
     }
 
     fn tile(&mut self, tile: &Tile) -> anyhow::Result<()> {
-        self.stdout
-            .queue(style::SetBackgroundColor(style::Color::AnsiValue(
-                tile.back,
-            )))?;
-        self.stdout
-            .queue(style::SetForegroundColor(style::Color::AnsiValue(
-                tile.fore,
-            )))?;
+        if self.back != Some(tile.back) {
+            self.stdout
+                .queue(style::SetBackgroundColor(style::Color::AnsiValue(
+                    tile.back,
+                )))?;
+            self.back = Some(tile.back);
+        }
+        if self.fore != Some(tile.fore) {
+            self.stdout
+                .queue(style::SetForegroundColor(style::Color::AnsiValue(
+                    tile.fore,
+                )))?;
+            self.fore = Some(tile.fore);
+        }
         self.stdout.queue(style::Print(tile.char))?;
         Ok(())
     }
@@ -126,41 +209,18 @@ impl Renderer for Terminal {
 
 impl GetInput for Terminal {
     fn get_input(&self) -> Input {
-        if let Some(key_code) = term_input_helper() {
-            match key_code {
-                KeyCode::Char('q') => Input::Quit,
-                KeyCode::Char('h') => Input::Left,
-                KeyCode::Char('j') => Input::Down,
-                KeyCode::Char('k') => Input::Up,
-                KeyCode::Char('l') => Input::Right,
-                KeyCode::Char('u') => Input::UpLeft,
-                KeyCode::Char('i') => Input::UpRight,
-                KeyCode::Char('n') => Input::DownLeft,
-                KeyCode::Char('m') => Input::DownRight,
-                KeyCode::Char('1') => Input::Number('1'),
-                KeyCode::Char('2') => Input::Number('2'),
-                KeyCode::Char('3') => Input::Number('3'),
-                KeyCode::Char('4') => Input::Number('4'),
-                KeyCode::Char('5') => Input::Number('5'),
-                KeyCode::Char('6') => Input::Number('6'),
-                KeyCode::Char('7') => Input::Number('7'),
-                KeyCode::Char('8') => Input::Number('8'),
-                KeyCode::Char('9') => Input::Number('9'),
-                KeyCode::Char('0') => Input::Number('0'),
-                KeyCode::Char(';') => Input::MenuPrev,
-                KeyCode::Char('\'') => Input::MenuNext,
-                KeyCode::Char('s') => Input::Select,
-                KeyCode::Char('t') => Input::EnterText,
-                _ => Input::None,
-            }
-        } else {
-            Input::None
+        if let Some(input) = self.pending.take() {
+            return input;
+        }
+        match self.term_input_helper() {
+            Some(key_event) => self.keymap.get(key_event),
+            None => Input::None,
         }
     }
 
     fn get_text_input(&self) -> TextInput {
-        if let Some(key_code) = term_input_helper() {
-            match key_code {
+        if let Some(key_event) = self.term_input_helper() {
+            match key_event.code {
                 KeyCode::Char(c) => TextInput::Char(c),
                 KeyCode::Backspace => TextInput::Backspace,
                 KeyCode::Esc => TextInput::Exit,
@@ -172,16 +232,33 @@ impl GetInput for Terminal {
     }
 }
 
-fn term_input_helper() -> Option<KeyCode> {
-    if poll(Duration::from_millis(50)).unwrap() {
-        match read().unwrap() {
-            Event::Key(key_event) => Some(key_event.code),
-            _ => None,
+impl Terminal {
+    fn term_input_helper(&self) -> Option<KeyEvent> {
+        if poll(Duration::from_millis(50)).unwrap() {
+            match read().unwrap() {
+                Event::Key(key_event) => Some(key_event),
+                Event::Resize(x, y) => {
+                    self.pending.set(Some(Input::Resize(x, y)));
+                    None
+                }
+                Event::Mouse(mouse_event) => {
+                    let (x, y) = (mouse_event.column, mouse_event.row);
+                    self.pending.set(match mouse_event.kind {
+                        MouseEventKind::Down(MouseButton::Left)
+                        | MouseEventKind::Drag(MouseButton::Left) => Some(Input::Click(x, y)),
+                        MouseEventKind::Down(MouseButton::Right) => Some(Input::RightClick(x, y)),
+                        _ => None,
+                    });
+                    None
+                }
+                _ => None,
+            }
+        } else {
+            None
         }
-    } else {
-        None
     }
 }
+
 fn text_bar(value: u8, end: u8, shaded: bool) -> String {
     let mut bar = "".to_string();
     for _i in 0..(value / 8) {
@@ -1,9 +1,25 @@
-#[derive(Debug, serde::Serialize, serde::Deserialize, Copy, Clone)]
+use crate::util::import_toml;
+use indexmap::IndexMap;
+
+// tiles the game places or looks up by name, so they have to be in tiles.toml
+pub const REQUIRED: [&str; 7] = [
+    "void",
+    "tile",
+    "brick_wall",
+    "door",
+    "door_open",
+    "stairs_up",
+    "stairs_down",
+];
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Copy, Clone, PartialEq)]
 pub struct Tile {
     pub r#char: char,
     pub fore: u8,
     pub back: u8,
     pub r#move: bool,
+    #[serde(default)]
+    pub opaque: bool,
 }
 
 impl Tile {
@@ -13,6 +29,16 @@ impl Tile {
             fore,
             back,
             r#move,
+            opaque: false,
+        }
+    }
+
+    // greyed out version for remembered tiles outside the field of view
+    pub fn dimmed(&self) -> Self {
+        Self {
+            fore: dim(self.fore),
+            back: dim(self.back),
+            ..*self
         }
     }
 
@@ -23,3 +49,34 @@ impl Tile {
             .collect()
     }
 }
+
+pub fn import_tiles(path: &str) -> anyhow::Result<IndexMap<String, Tile>> {
+    let tiles = import_toml::<Tile>(path)?;
+    if let Some(name) = REQUIRED.iter().find(|x| !tiles.contains_key(**x)) {
+        anyhow::bail!("{path} has no `{name}` tile, which the game needs");
+    }
+    Ok(tiles)
+}
+
+// maps an ansi 256 colour to a dark grey of similar brightness
+fn dim(color: u8) -> u8 {
+    const CUBE: [u16; 6] = [0, 95, 135, 175, 215, 255];
+    let brightness = match color {
+        0 => 0,
+        1..=6 => 64,
+        7 => 192,
+        8 => 128,
+        9..=14 => 160,
+        15 => 255,
+        16..=231 => {
+            let c = color as usize - 16;
+            (CUBE[c / 36] + CUBE[c / 6 % 6] + CUBE[c % 6]) / 3
+        }
+        232..=255 => 8 + 10 * (color as u16 - 232),
+    };
+    if brightness == 0 {
+        return 16;
+    }
+    // darkest quarter of the grey ramp
+    232 + (brightness * 6 / 255) as u8
+}
@@ -0,0 +1,51 @@
+// every entity gains its speed in energy each tick and may act while its
+// energy is not negative, acting costs energy so a cost of 100 at speed 10
+// takes 10 ticks to recover from
+pub const TICKS_PER_TURN: f32 = 10.0;
+const DEFAULT_COST: i32 = 100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    Move,
+    Attack,
+    Eat,
+    Cook,
+    Pickup,
+    Drop,
+    Wait,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Move,
+        Action::Attack,
+        Action::Eat,
+        Action::Cook,
+        Action::Pickup,
+        Action::Drop,
+        Action::Wait,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Move => "move",
+            Action::Attack => "attack",
+            Action::Eat => "eat",
+            Action::Cook => "cook",
+            Action::Pickup => "pickup",
+            Action::Drop => "drop",
+            Action::Wait => "wait",
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ActionCost {
+    pub cost: i32,
+}
+
+pub fn base_cost(costs: &indexmap::IndexMap<String, ActionCost>, action: Action) -> i32 {
+    costs
+        .get(action.name())
+        .map_or(DEFAULT_COST, |action| action.cost)
+}
@@ -59,11 +59,21 @@ impl Menu {
     }
 
     pub fn next(&mut self) {
-        if self.selection < self.items.len() - 1 {
+        if self.selection + 1 < self.items.len() {
             self.selection += 1;
         }
     }
 
+    // the item drawn at a display position, if any
+    pub fn item_at(&self, position: glam::U16Vec2) -> Option<usize> {
+        let offset = position.as_i16vec2() - self.position.as_i16vec2();
+        if offset.x < 1 || offset.x >= self.size.x as i16 || offset.y < 1 {
+            return None;
+        }
+        let item = offset.y as usize - 1;
+        (item < self.items.len() && offset.y < self.size.y as i16).then_some(item)
+    }
+
     pub fn prev(&mut self) {
         if self.selection > 0 {
             self.selection -= 1;
@@ -81,6 +91,11 @@ impl Ui for Menu {
     }
 
     fn render_to(&self, display: &mut Display) {
+        // skip drawing rather than index past a display that has shrunk
+        let corner = self.position + self.size;
+        if corner.x >= display.size.x || corner.y >= display.size.y {
+            return;
+        }
         let tile_void = Tile::new(' ', 0, 0, true);
         let name = Tile::from_string(&self.name, Some(15), Some(0));
 
@@ -104,13 +119,14 @@ impl Ui for Menu {
             display[self.position + glam::u16vec2(self.size.x, row)] =
                 Tile::new(LINE_VERT, 15, 0, true);
         }
-        for (item_i, row) in (2..=self.size.y).enumerate() {
+        for (item_i, row) in (1..self.size.y).enumerate() {
             if item_i < self.items.len() {
                 let item = &self.items[item_i];
-                for (index, col) in (2..=self.size.x).enumerate() {
+                for (index, col) in (1..self.size.x).enumerate() {
                     const POINTER_OFFSET: usize = 2;
+                    let position = self.position + glam::u16vec2(col, row);
                     if index < POINTER_OFFSET {
-                        display.data[row as usize][col as usize] = match index {
+                        display[position] = match index {
                             0 => {
                                 if self.selection == item_i {
                                     Tile::new('>', 15, 0, true)
@@ -122,14 +138,14 @@ impl Ui for Menu {
                             _ => panic!(),
                         }
                     } else if index < item.len() + POINTER_OFFSET {
-                        display.data[row as usize][col as usize] = item[index - 2];
+                        display[position] = item[index - 2];
                     } else {
-                        display.data[row as usize][col as usize] = tile_void;
+                        display[position] = tile_void;
                     }
                 }
             } else {
-                for col in 2..=self.size.x {
-                    display.data[row as usize][col as usize] = tile_void;
+                for col in 1..self.size.x {
+                    display[self.position + glam::u16vec2(col, row)] = tile_void;
                 }
             }
         }
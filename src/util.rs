@@ -1,17 +1,41 @@
+use anyhow::Context;
 use indexmap::IndexMap;
 
-pub fn import_toml<T: serde::de::DeserializeOwned>(path: &str) -> IndexMap<String, T> {
-    let mut tiles: IndexMap<String, T> = IndexMap::new();
-    let table: toml::Table = std::fs::read_to_string(path)
-        .unwrap()
-        .parse::<toml::Table>()
-        .unwrap();
-    let tile_names = table.keys().collect::<Vec<_>>();
-    for tile_name in tile_names.into_iter() {
-        let tile: T = toml::from_str(&toml::to_string(&table[tile_name]).unwrap()).unwrap();
-        tiles.insert(tile_name.clone(), tile);
-    }
-    tiles
+// chacha output is stable across platforms and rand versions, so a seed always gives the same dungeon
+pub type GameRng = rand_chacha::ChaCha8Rng;
+
+// entries keep the order they have in the file
+pub fn import_toml<T: serde::de::DeserializeOwned>(
+    path: &str,
+) -> anyhow::Result<IndexMap<String, T>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("could not read {path}"))?;
+    parse_toml(path, &text)
+}
+
+pub fn parse_toml<T: serde::de::DeserializeOwned>(path: &str, text: &str) -> anyhow::Result<T> {
+    toml::from_str(text).map_err(|error| {
+        let entry = error
+            .span()
+            .and_then(|span| entry_at(text, span.start))
+            .map(|entry| format!(" in entry `{entry}`"))
+            .unwrap_or_default();
+        anyhow::Error::new(error).context(format!("could not load {path}{entry}"))
+    })
+}
+
+// the [table] a byte offset falls in, or the top level key on its line when there is no table
+fn entry_at(text: &str, offset: usize) -> Option<&str> {
+    let offset = offset.min(text.len());
+    // the error can point at a table's own header, so its whole line is searched
+    let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+    let mut lines = text.get(..end)?.lines().rev().map(str::trim);
+    let line = lines.next()?;
+    std::iter::once(line)
+        .chain(lines)
+        .find(|line| line.starts_with('[') && !line.starts_with("[["))
+        .and_then(|line| line.strip_prefix('[')?.split(']').next())
+        .or_else(|| line.split('=').next())
+        .map(|name| name.trim().trim_matches('"'))
 }
 
 #[derive(Clone, Debug)]
@@ -79,3 +103,46 @@ impl<T: Clone> IndexMut<usize> for Quadtree<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Colours {
+        fore: u8,
+        back: u8,
+    }
+
+    fn error<T: serde::de::DeserializeOwned + std::fmt::Debug>(text: &str) -> String {
+        parse_toml::<T>("test.toml", text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn names_entry_missing_a_field() {
+        let text = "[void]\nfore = 0\nback = 0\n\n[grass]\nback = 2\n";
+        assert_eq!(
+            error::<IndexMap<String, Colours>>(text),
+            "could not load test.toml in entry `grass`"
+        );
+    }
+
+    #[test]
+    fn names_entry_with_a_wrong_type() {
+        let text = "[void]\nfore = 0\nback = 0\n\n[grass]\nfore = \"green\"\nback = 2\n";
+        assert_eq!(
+            error::<IndexMap<String, Colours>>(text),
+            "could not load test.toml in entry `grass`"
+        );
+    }
+
+    #[test]
+    fn names_top_level_key() {
+        let text = "a = 1\n\"b\" = \"two\"\nc = 3\n";
+        assert_eq!(
+            error::<IndexMap<String, u8>>(text),
+            "could not load test.toml in entry `b`"
+        );
+    }
+}
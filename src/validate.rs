@@ -0,0 +1,107 @@
+use crate::entity::Entity;
+use crate::level::{RoomTile, ROOM_SIZES};
+use crate::tile::{self, Tile};
+use crate::turn::{Action, ActionCost};
+use indexmap::IndexMap;
+
+// every problem found in the resource files, each naming the file and entry
+pub fn validate() -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let tiles = check_entries::<Tile>("res/tiles.toml", &mut problems);
+    for name in tile::REQUIRED {
+        if !tiles.iter().any(|(x, _)| x == name) {
+            problems.push(format!(
+                "res/tiles.toml: no `{name}` entry, the game needs it"
+            ));
+        }
+    }
+    let entities = check_entries::<Entity>("res/entity.toml", &mut problems);
+    for (name, entity) in entities.iter() {
+        if entity.speed <= 0 {
+            problems.push(format!("res/entity.toml: {name}: speed must be above 0"));
+        }
+    }
+    if !entities.iter().any(|(name, _)| name == "player") {
+        problems.push("res/entity.toml: no `player` entry".to_string());
+    }
+
+    for (name, cost) in check_entries::<ActionCost>("res/actions.toml", &mut problems) {
+        if cost.cost <= 0 {
+            problems.push(format!("res/actions.toml: {name}: cost must be above 0"));
+        }
+        if !Action::ALL.iter().any(|x| x.name() == name) {
+            problems.push(format!("res/actions.toml: {name}: no such action"));
+        }
+    }
+
+    for size in 0..ROOM_SIZES {
+        let path = format!("res/room_size_{size}.toml");
+        for (name, room) in check_entries::<RoomTile>(&path, &mut problems) {
+            let mut problem = |text: String| problems.push(format!("{path}: {name}: {text}"));
+            if room.size != size {
+                problem(format!(
+                    "size is {} but the file is for size {size}",
+                    room.size
+                ));
+            }
+            for tile in room.tiles.iter() {
+                if !tiles.iter().any(|(x, _)| x == tile) {
+                    problem(format!("unknown tile `{tile}`"));
+                }
+            }
+            let span = (1 << size) + 1;
+            if room.data.len() != span {
+                problem(format!("has {} rows, expected {span}", room.data.len()));
+            }
+            for (i, row) in room.data.iter().enumerate() {
+                if row.len() != span {
+                    problem(format!(
+                        "row {i} has {} columns, expected {span}",
+                        row.len()
+                    ));
+                }
+                for (j, index) in row.iter().enumerate() {
+                    if *index >= room.tiles.len() {
+                        problem(format!(
+                            "row {i} column {j} uses tile index {index} but only {} tiles are listed",
+                            room.tiles.len()
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+// the entries that parsed, with a problem recorded for each one that did not
+fn check_entries<T: serde::de::DeserializeOwned>(
+    path: &str,
+    problems: &mut Vec<String>,
+) -> Vec<(String, T)> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) => {
+            problems.push(format!("{path}: {error}"));
+            return Vec::new();
+        }
+    };
+    // an IndexMap keeps the problems in file order
+    let table = match toml::from_str::<IndexMap<String, toml::Value>>(&text) {
+        Ok(table) => table,
+        Err(error) => {
+            problems.push(format!("{path}: {error}"));
+            return Vec::new();
+        }
+    };
+    let mut entries = Vec::new();
+    for (name, value) in table {
+        match T::deserialize(value) {
+            Ok(entry) => entries.push((name, entry)),
+            Err(error) => problems.push(format!("{path}: {name}: {}", error.message())),
+        }
+    }
+    entries
+}